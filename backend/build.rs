@@ -0,0 +1,4 @@
+fn main() {
+    // Re-embed migrations whenever the directory changes
+    println!("cargo:rerun-if-changed=migrations");
+}
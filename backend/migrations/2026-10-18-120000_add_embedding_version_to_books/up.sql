@@ -0,0 +1,3 @@
+-- Identifies the embedder (model and parameters) that produced each vector,
+-- so vectors from different embedders are never compared with each other
+ALTER TABLE books ADD COLUMN embedding_version VARCHAR;
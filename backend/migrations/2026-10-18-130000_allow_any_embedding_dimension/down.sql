@@ -0,0 +1,9 @@
+DROP INDEX book_embedding_384_cosine_index;
+DROP INDEX book_embedding_512_cosine_index;
+DROP INDEX book_embedding_768_cosine_index;
+
+-- Vectors of other sizes cannot be kept; `embeddings reembed` recomputes them
+UPDATE books SET embedding = NULL, embedding_version = NULL
+    WHERE vector_dims(embedding) <> 512;
+ALTER TABLE books ALTER COLUMN embedding TYPE VECTOR(512);
+CREATE INDEX book_embedding_cosine_index ON books USING hnsw (embedding vector_cosine_ops);
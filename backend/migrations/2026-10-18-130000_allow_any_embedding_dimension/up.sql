@@ -0,0 +1,17 @@
+-- Let books.embedding hold vectors of any dimension, so embedders such as
+-- sentence-transformers (384 or 768 dimensions) can share the column with
+-- the 512-dimensional hashing embedder
+DROP INDEX book_embedding_cosine_index;
+ALTER TABLE books ALTER COLUMN embedding TYPE VECTOR;
+
+-- HNSW indexes need a fixed dimension, so each supported size gets its own
+-- partial index over the column cast to that size
+CREATE INDEX book_embedding_384_cosine_index ON books
+    USING hnsw ((embedding::vector(384)) vector_cosine_ops)
+    WHERE vector_dims(embedding) = 384;
+CREATE INDEX book_embedding_512_cosine_index ON books
+    USING hnsw ((embedding::vector(512)) vector_cosine_ops)
+    WHERE vector_dims(embedding) = 512;
+CREATE INDEX book_embedding_768_cosine_index ON books
+    USING hnsw ((embedding::vector(768)) vector_cosine_ops)
+    WHERE vector_dims(embedding) = 768;
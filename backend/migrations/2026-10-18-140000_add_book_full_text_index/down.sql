@@ -0,0 +1 @@
+DROP INDEX book_full_text_index;
@@ -0,0 +1,4 @@
+-- Full-text index over title and abstract for lexical search. Queries must
+-- repeat this exact expression for Postgres to use the index.
+CREATE INDEX book_full_text_index ON books
+    USING gin (to_tsvector('portuguese', title || ' ' || abstract_text));
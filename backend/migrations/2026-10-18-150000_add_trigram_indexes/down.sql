@@ -0,0 +1,3 @@
+DROP INDEX book_title_trgm_index;
+DROP INDEX author_name_trgm_index;
+DROP EXTENSION IF EXISTS pg_trgm;
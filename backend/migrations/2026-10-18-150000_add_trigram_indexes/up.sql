@@ -0,0 +1,6 @@
+-- Trigram indexes for typo-tolerant author and title search. gin_trgm_ops
+-- serves both the word-similarity operator (<%) and ILIKE '%...%'.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX author_name_trgm_index ON authors USING gin (name gin_trgm_ops);
+CREATE INDEX book_title_trgm_index ON books USING gin (title gin_trgm_ops);
@@ -0,0 +1,7 @@
+ALTER TABLE books_authors
+  DROP CONSTRAINT books_authors_book_id_fkey,
+  ADD CONSTRAINT books_authors_book_id_fkey
+    FOREIGN KEY (book_id) REFERENCES books(id),
+  DROP CONSTRAINT books_authors_author_id_fkey,
+  ADD CONSTRAINT books_authors_author_id_fkey
+    FOREIGN KEY (author_id) REFERENCES authors(id);
@@ -0,0 +1,9 @@
+-- Deleting a book removes its links to authors; deleting an author is
+-- refused while books still link to them.
+ALTER TABLE books_authors
+  DROP CONSTRAINT books_authors_book_id_fkey,
+  ADD CONSTRAINT books_authors_book_id_fkey
+    FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE,
+  DROP CONSTRAINT books_authors_author_id_fkey,
+  ADD CONSTRAINT books_authors_author_id_fkey
+    FOREIGN KEY (author_id) REFERENCES authors(id) ON DELETE RESTRICT;
@@ -0,0 +1,25 @@
+DROP TRIGGER authors_touch ON authors;
+DROP TRIGGER authors_history ON authors;
+DROP TRIGGER books_touch ON books;
+DROP TRIGGER books_history ON books;
+DROP TABLE record_history;
+DROP FUNCTION reject_history_change();
+DROP FUNCTION record_catalog_version();
+DROP FUNCTION touch_catalog_row();
+DROP FUNCTION catalog_snapshot(JSONB);
+
+-- Fails while a deleted record shares its name or title with another one
+DROP INDEX books_live_title_key;
+ALTER TABLE books ADD CONSTRAINT books_title_key UNIQUE (title);
+DROP INDEX authors_live_name_key;
+ALTER TABLE authors ADD CONSTRAINT authors_name_key UNIQUE (name);
+
+ALTER TABLE books
+  DROP COLUMN created_at,
+  DROP COLUMN updated_at,
+  DROP COLUMN deleted_at;
+
+ALTER TABLE authors
+  DROP COLUMN created_at,
+  DROP COLUMN updated_at,
+  DROP COLUMN deleted_at;
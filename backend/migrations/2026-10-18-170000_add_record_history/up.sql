@@ -0,0 +1,103 @@
+-- Soft delete and change history for authors and books. Deleting a record
+-- only sets deleted_at, and every version of a record is kept, as JSON, in
+-- record_history by triggers, so any way of writing to the tables is
+-- recorded. The actor is read from the transaction-local setting
+-- icarusp.actor.
+ALTER TABLE authors
+  ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  ADD COLUMN deleted_at TIMESTAMPTZ;
+
+ALTER TABLE books
+  ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  ADD COLUMN deleted_at TIMESTAMPTZ;
+
+-- Names and titles stay unique among records that are not deleted, so a
+-- deleted record does not hold its name back; restoring it while another
+-- record took the name fails on these indexes
+ALTER TABLE authors DROP CONSTRAINT authors_name_key;
+CREATE UNIQUE INDEX authors_live_name_key ON authors (name) WHERE deleted_at IS NULL;
+ALTER TABLE books DROP CONSTRAINT books_title_key;
+CREATE UNIQUE INDEX books_live_title_key ON books (title) WHERE deleted_at IS NULL;
+
+CREATE TABLE record_history (
+  id SERIAL PRIMARY KEY,
+  record_table VARCHAR NOT NULL CHECK (record_table IN ('authors', 'books')),
+  record_id INTEGER NOT NULL,
+  version INTEGER NOT NULL,
+  action VARCHAR NOT NULL CHECK (action IN ('create', 'update', 'delete', 'restore')),
+  data JSONB NOT NULL,
+  actor VARCHAR NOT NULL,
+  recorded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  UNIQUE (record_table, record_id, version)
+);
+
+-- The content of a row as kept in its history: every column but the id,
+-- the timestamps and the embedding, which is derived from the text
+CREATE FUNCTION catalog_snapshot(row_data JSONB) RETURNS JSONB AS $$
+  SELECT row_data - 'id' - 'created_at' - 'updated_at' - 'deleted_at'
+    - 'embedding' - 'embedding_version'
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE FUNCTION touch_catalog_row() RETURNS trigger AS $$
+BEGIN
+  IF catalog_snapshot(to_jsonb(NEW)) IS DISTINCT FROM catalog_snapshot(to_jsonb(OLD))
+    OR NEW.deleted_at IS DISTINCT FROM OLD.deleted_at THEN
+    NEW.updated_at := now();
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION record_catalog_version() RETURNS trigger AS $$
+DECLARE
+  change VARCHAR;
+BEGIN
+  IF TG_OP = 'INSERT' THEN
+    change := 'create';
+  ELSIF NEW.deleted_at IS NOT NULL AND OLD.deleted_at IS NULL THEN
+    change := 'delete';
+  ELSIF NEW.deleted_at IS NULL AND OLD.deleted_at IS NOT NULL THEN
+    change := 'restore';
+  ELSIF catalog_snapshot(to_jsonb(NEW)) IS DISTINCT FROM catalog_snapshot(to_jsonb(OLD)) THEN
+    change := 'update';
+  ELSE
+    -- Only the embedding or a timestamp changed
+    RETURN NULL;
+  END IF;
+
+  INSERT INTO record_history (record_table, record_id, version, action, data, actor)
+  SELECT TG_TABLE_NAME, NEW.id, COALESCE(MAX(version), 0) + 1, change,
+    catalog_snapshot(to_jsonb(NEW)),
+    COALESCE(NULLIF(current_setting('icarusp.actor', true), ''), 'system')
+  FROM record_history
+  WHERE record_table = TG_TABLE_NAME AND record_id = NEW.id;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION reject_history_change() RETURNS trigger AS $$
+BEGIN
+  RAISE EXCEPTION 'record_history is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER authors_touch BEFORE UPDATE ON authors
+  FOR EACH ROW EXECUTE FUNCTION touch_catalog_row();
+CREATE TRIGGER authors_history AFTER INSERT OR UPDATE ON authors
+  FOR EACH ROW EXECUTE FUNCTION record_catalog_version();
+CREATE TRIGGER books_touch BEFORE UPDATE ON books
+  FOR EACH ROW EXECUTE FUNCTION touch_catalog_row();
+CREATE TRIGGER books_history AFTER INSERT OR UPDATE ON books
+  FOR EACH ROW EXECUTE FUNCTION record_catalog_version();
+CREATE TRIGGER record_history_append_only BEFORE UPDATE OR DELETE ON record_history
+  FOR EACH ROW EXECUTE FUNCTION reject_history_change();
+
+-- Records stored before history was kept start from their current version
+INSERT INTO record_history (record_table, record_id, version, action, data, actor)
+SELECT 'authors', id, 1, 'create', catalog_snapshot(to_jsonb(authors)), 'migration'
+FROM authors;
+INSERT INTO record_history (record_table, record_id, version, action, data, actor)
+SELECT 'books', id, 1, 'create', catalog_snapshot(to_jsonb(books)), 'migration'
+FROM books;
@@ -0,0 +1,122 @@
+//! Command-line parsing for the backend binary
+
+use anyhow::Result;
+
+pub const USAGE: &str = "\
+Usage:
+  backend [--migrate]          Start the server (--migrate applies pending migrations first)
+  backend migrate up           Apply all pending migrations
+  backend migrate down         Revert the most recently applied migration
+  backend migrate status       List migrations and whether they have been applied
+  backend embeddings reembed   Recompute embeddings produced by a different embedder version
+  backend embeddings refit     Refit the TF-IDF vocabulary over all abstracts and re-embed every book";
+
+/// Action requested through the `migrate` subcommand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MigrateAction {
+    Up,
+    Down,
+    Status,
+}
+
+/// Action requested through the `embeddings` subcommand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmbeddingsAction {
+    Reembed,
+    Refit,
+}
+
+/// What the binary was asked to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Start the HTTP server, optionally applying pending migrations first
+    Serve { migrate: bool },
+    /// Manage database migrations and exit
+    Migrate(MigrateAction),
+    /// Maintain stored book embeddings and exit
+    Embeddings(EmbeddingsAction),
+    /// Print usage and exit
+    Help,
+}
+
+/// Parses the command-line arguments (excluding the program name)
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command> {
+    let args: Vec<String> = args.into_iter().collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        [] => Ok(Command::Serve { migrate: false }),
+        ["--migrate"] => Ok(Command::Serve { migrate: true }),
+        ["-h" | "--help" | "help"] => Ok(Command::Help),
+        ["migrate", action] => match *action {
+            "up" => Ok(Command::Migrate(MigrateAction::Up)),
+            "down" => Ok(Command::Migrate(MigrateAction::Down)),
+            "status" => Ok(Command::Migrate(MigrateAction::Status)),
+            other => Err(anyhow::anyhow!("Unknown migrate action: {}", other)),
+        },
+        ["migrate"] => Err(anyhow::anyhow!(
+            "Missing migrate action (expected up, down or status)"
+        )),
+        ["embeddings", "reembed"] => Ok(Command::Embeddings(EmbeddingsAction::Reembed)),
+        ["embeddings", "refit"] => Ok(Command::Embeddings(EmbeddingsAction::Refit)),
+        ["embeddings", other] => Err(anyhow::anyhow!("Unknown embeddings action: {}", other)),
+        ["embeddings"] => Err(anyhow::anyhow!(
+            "Missing embeddings action (expected reembed or refit)"
+        )),
+        other => Err(anyhow::anyhow!("Unknown arguments: {}", other.join(" "))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_serve() {
+        assert_eq!(parse(&[]).unwrap(), Command::Serve { migrate: false });
+        assert_eq!(
+            parse(&["--migrate"]).unwrap(),
+            Command::Serve { migrate: true }
+        );
+    }
+
+    #[test]
+    fn test_parse_migrate() {
+        assert_eq!(
+            parse(&["migrate", "up"]).unwrap(),
+            Command::Migrate(MigrateAction::Up)
+        );
+        assert_eq!(
+            parse(&["migrate", "down"]).unwrap(),
+            Command::Migrate(MigrateAction::Down)
+        );
+        assert_eq!(
+            parse(&["migrate", "status"]).unwrap(),
+            Command::Migrate(MigrateAction::Status)
+        );
+    }
+
+    #[test]
+    fn test_parse_embeddings() {
+        assert_eq!(
+            parse(&["embeddings", "reembed"]).unwrap(),
+            Command::Embeddings(EmbeddingsAction::Reembed)
+        );
+        assert_eq!(
+            parse(&["embeddings", "refit"]).unwrap(),
+            Command::Embeddings(EmbeddingsAction::Refit)
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse(&["migrate"]).is_err());
+        assert!(parse(&["embeddings"]).is_err());
+        assert!(parse(&["migrate", "sideways"]).is_err());
+        assert!(parse(&["--serve"]).is_err());
+    }
+}
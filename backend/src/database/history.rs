@@ -0,0 +1,332 @@
+//! Versions of authors and books. The database writes them itself, through
+//! the triggers of the `add_record_history` migration, on every insert and
+//! update; this module names who makes a change, reads the versions back
+//! and restores them.
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::Deserialize;
+
+use crate::database::modification::{check_birth_year, check_publication_year};
+use crate::database::query::{AuthorOutput, BookOutput, set_local, with_authors};
+use crate::embedding::Embedder;
+use crate::error::{Error, Result, ResultExt};
+use crate::models::{Author, AuthorChanges, Book, BookChanges, HistoryEntry, RecordTable};
+use crate::schema::{authors, books, record_history};
+
+/// Transaction-local setting the history triggers read the actor from
+const ACTOR_SETTING: &str = "icarusp.actor";
+
+/// Runs `change` in a transaction whose versions of authors and books are
+/// recorded as made by `actor`. Changes made outside of it, e.g. from
+/// `psql`, are recorded as made by `system`.
+pub fn as_actor<T>(
+    conn: &mut PgConnection,
+    actor: &str,
+    change: impl FnOnce(&mut PgConnection) -> Result<T>,
+) -> Result<T> {
+    conn.transaction(|conn| {
+        set_local(conn, ACTOR_SETTING, actor).context("Failed to set the actor")?;
+        change(conn)
+    })
+}
+
+/// Lists every version of a record, oldest first. Deleted records keep
+/// their history.
+pub fn get_history(
+    conn: &mut PgConnection,
+    table: RecordTable,
+    record_id: i32,
+) -> Result<Vec<HistoryEntry>> {
+    let history = record_history::table
+        .filter(record_history::record_table.eq(table.name()))
+        .filter(record_history::record_id.eq(record_id))
+        .order(record_history::version)
+        .select(HistoryEntry::as_select())
+        .load(conn)
+        .with_context(|| format!("Failed to query history of {} {}", table.name(), record_id))?;
+
+    if history.is_empty() {
+        return Err(Error::not_found(table.entity(), record_id));
+    }
+    Ok(history)
+}
+
+/// One version of a record
+pub fn get_version(
+    conn: &mut PgConnection,
+    table: RecordTable,
+    record_id: i32,
+    version: i32,
+) -> Result<HistoryEntry> {
+    record_history::table
+        .filter(record_history::record_table.eq(table.name()))
+        .filter(record_history::record_id.eq(record_id))
+        .filter(record_history::version.eq(version))
+        .select(HistoryEntry::as_select())
+        .first(conn)
+        .optional()
+        .with_context(|| format!("Failed to query history of {} {}", table.name(), record_id))?
+        .ok_or_else(|| {
+            Error::NotFound(format!(
+                "{} with ID {} has no version {}",
+                table.entity(),
+                record_id,
+                version
+            ))
+        })
+}
+
+/// The fields of an author kept in its history
+#[derive(Deserialize)]
+struct AuthorVersion {
+    name: String,
+    birth_year: i32,
+    country: String,
+}
+
+/// The fields of a book kept in its history
+#[derive(Deserialize)]
+pub struct BookVersion {
+    title: String,
+    publication_year: i32,
+    abstract_text: String,
+}
+
+impl BookVersion {
+    /// Changes that bring a book back to this version. The abstract is
+    /// embedded again with `embedder`, so call this before locking the book.
+    pub fn changes(&self, embedder: &dyn Embedder) -> Result<BookChanges> {
+        BookChanges::new(
+            Some(&self.title),
+            Some(self.publication_year),
+            Some(&self.abstract_text),
+            embedder,
+        )
+        .context("Failed to embed book abstract")
+    }
+}
+
+/// Reads the fields of `entry`
+fn fields<T: serde::de::DeserializeOwned>(entry: HistoryEntry) -> Result<T> {
+    let version = entry.version;
+    serde_json::from_value(entry.data)
+        .map_err(anyhow::Error::from)
+        .with_context(|| format!("Failed to read version {}", version))
+}
+
+/// Brings an author back to `version`, undeleting it if needed, and
+/// returns it. The restore is itself recorded as a new version.
+pub fn restore_author(
+    conn: &mut PgConnection,
+    author_id: i32,
+    version: i32,
+) -> Result<AuthorOutput> {
+    conn.transaction(|conn| {
+        let fields: AuthorVersion =
+            fields(get_version(conn, RecordTable::Authors, author_id, version)?)?;
+        authors::table
+            .find(author_id)
+            .for_update()
+            .select(authors::id)
+            .first::<i32>(conn)
+            .optional()
+            .with_context(|| format!("Failed to query author {}", author_id))?
+            .ok_or_else(|| Error::not_found("Author", author_id))?;
+        check_birth_year(conn, author_id, fields.birth_year)?;
+
+        let changes = AuthorChanges {
+            name: Some(fields.name),
+            birth_year: Some(fields.birth_year),
+            country: Some(fields.country),
+        };
+        let author = diesel::update(authors::table.find(author_id))
+            .set((&changes, authors::deleted_at.eq(None::<DateTime<Utc>>)))
+            .returning(Author::as_returning())
+            .get_result(conn)
+            .with_context(|| format!("Failed to restore author {}", author_id))?;
+        Ok(AuthorOutput::from(author))
+    })
+}
+
+/// The fields of one version of a book
+pub fn get_book_version(
+    conn: &mut PgConnection,
+    book_id: i32,
+    version: i32,
+) -> Result<BookVersion> {
+    fields(get_version(conn, RecordTable::Books, book_id, version)?)
+}
+
+/// Brings a book back to the version `changes` come from (see
+/// [`BookVersion::changes`]), undeleting it if needed, and returns it with
+/// its authors. The restore is itself recorded as a new version.
+pub fn restore_book(
+    conn: &mut PgConnection,
+    book_id: i32,
+    changes: &BookChanges,
+) -> Result<BookOutput> {
+    conn.transaction(|conn| {
+        books::table
+            .find(book_id)
+            .for_update()
+            .select(books::id)
+            .first::<i32>(conn)
+            .optional()
+            .with_context(|| format!("Failed to query book {}", book_id))?
+            .ok_or_else(|| Error::not_found("Book", book_id))?;
+        if let Some(publication_year) = changes.publication_year {
+            check_publication_year(conn, book_id, publication_year)?;
+        }
+
+        let book = diesel::update(books::table.find(book_id))
+            .set((changes, books::deleted_at.eq(None::<DateTime<Utc>>)))
+            .returning(Book::as_returning())
+            .get_result(conn)
+            .with_context(|| format!("Failed to restore book {}", book_id))?;
+        Ok(with_authors(conn, vec![book])?.remove(0))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::initialization::establish_connection;
+    use crate::database::insertion::{insert_author, insert_book};
+    use crate::database::modification::{delete_author, update_author, update_book};
+    use crate::database::query::get_author_by_id;
+    use crate::embedding::HashingEmbedder;
+    use crate::models::{NewAuthor, NewBook};
+    use pgvector::Vector;
+
+    // Helper function to check if database is available
+    fn db_available() -> bool {
+        std::env::var("DATABASE_URL").is_ok()
+    }
+
+    #[test]
+    fn test_history_and_restore_author() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let author = as_actor(&mut conn, "ana", |conn| {
+            insert_author(conn, &NewAuthor::new("History Author", 1900, "Chile"))
+        })
+        .expect("Failed to insert author");
+        let changes = AuthorChanges {
+            country: Some("Peru".to_string()),
+            ..AuthorChanges::default()
+        };
+        as_actor(&mut conn, "bia", |conn| {
+            update_author(conn, author.id, &changes)
+        })
+        .unwrap();
+        let deleted = delete_author(&mut conn, author.id).unwrap();
+        assert!(deleted.deleted_at.is_some());
+        assert!(matches!(
+            get_author_by_id(&mut conn, author.id),
+            Err(Error::NotFound(_))
+        ));
+
+        let history = get_history(&mut conn, RecordTable::Authors, author.id).unwrap();
+        let summary: Vec<(i32, &str, &str)> = history
+            .iter()
+            .map(|entry| (entry.version, entry.action.as_str(), entry.actor.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "create", "ana"),
+                (2, "update", "bia"),
+                (3, "delete", "system")
+            ]
+        );
+        assert_eq!(history[1].data["country"], "Peru");
+
+        // The name of a deleted author is free again, and restoring the
+        // author clashes with whoever took it
+        let namesake = insert_author(&mut conn, &NewAuthor::new("History Author", 1950, "Chile"))
+            .expect("Failed to reuse the name of a deleted author");
+        assert!(matches!(
+            restore_author(&mut conn, author.id, 1),
+            Err(Error::Conflict(_))
+        ));
+        diesel::delete(authors::table.find(namesake.id))
+            .execute(&mut conn)
+            .unwrap();
+
+        // Restoring the first version undeletes the author as it was created
+        let restored =
+            as_actor(&mut conn, "ana", |conn| restore_author(conn, author.id, 1)).unwrap();
+        assert_eq!(restored.country, "Chile");
+        assert!(restored.deleted_at.is_none());
+        assert_eq!(
+            get_author_by_id(&mut conn, author.id).unwrap().country,
+            "Chile"
+        );
+        let history = get_history(&mut conn, RecordTable::Authors, author.id).unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[3].action, "restore");
+
+        assert!(matches!(
+            restore_author(&mut conn, author.id, 9),
+            Err(Error::NotFound(_))
+        ));
+
+        // Cleanup
+        diesel::delete(authors::table.filter(authors::id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_restore_book_embeds_old_abstract() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+        let original = "The first summary of the history book";
+        let new_book = NewBook::new("History Book", 1950, original, &embedder).unwrap();
+        let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+        let changes =
+            BookChanges::new(None, None, Some("A second, unrelated summary"), &embedder).unwrap();
+        update_book(&mut conn, book.id, &changes).unwrap();
+
+        // Re-embedding alone leaves no version behind
+        diesel::update(books::table.find(book.id))
+            .set(books::embedding_version.eq("other"))
+            .execute(&mut conn)
+            .unwrap();
+        assert_eq!(
+            get_history(&mut conn, RecordTable::Books, book.id)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let changes = get_book_version(&mut conn, book.id, 1)
+            .unwrap()
+            .changes(&embedder)
+            .unwrap();
+        let restored = restore_book(&mut conn, book.id, &changes).unwrap();
+        assert_eq!(restored.abstract_text, original);
+        let embedding: Option<Vector> = books::table
+            .find(book.id)
+            .select(books::embedding)
+            .first(&mut conn)
+            .unwrap();
+        assert_eq!(
+            embedding.unwrap().to_vec(),
+            embedder.embed(original).unwrap()
+        );
+
+        // Cleanup
+        diesel::delete(books::table.filter(books::id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+}
@@ -1,8 +1,10 @@
-use anyhow::Result;
 use diesel::prelude::*;
 
+use crate::database::query::{BookOutput, with_authors};
+use crate::error::{Error, Result, ResultExt};
 use crate::models::{Author, Book, BookAuthor, NewAuthor, NewBook, NewBookAuthor};
 use crate::schema::{authors, books, books_authors};
+use crate::validation::Validator;
 
 /// Inserts a new Author into the database and returns the created Author
 pub fn insert_author(conn: &mut PgConnection, new_author: &NewAuthor) -> Result<Author> {
@@ -10,7 +12,7 @@ pub fn insert_author(conn: &mut PgConnection, new_author: &NewAuthor) -> Result<
         .values(new_author)
         .returning(Author::as_returning())
         .get_result(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to insert author: {}", e))?;
+        .context("Failed to insert author")?;
 
     Ok(author)
 }
@@ -21,7 +23,7 @@ pub fn insert_book(conn: &mut PgConnection, new_book: &NewBook) -> Result<Book>
         .values(new_book)
         .returning(Book::as_returning())
         .get_result(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to insert book: {}", e))?;
+        .context("Failed to insert book")?;
 
     Ok(book)
 }
@@ -38,52 +40,128 @@ pub fn link_book_author(
         .values(&new_book_author)
         .returning(BookAuthor::as_returning())
         .get_result(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to link book and author: {}", e))?;
+        .context("Failed to link book and author")?;
 
     Ok(book_author)
 }
 
-/// Links a book to multiple authors by their IDs
-/// Returns a vector of created BookAuthor relationships
+/// Links a book to multiple authors by their IDs in one transaction,
+/// refusing unknown authors and authors born after the book was published.
+/// Repeated IDs are linked once and links that already exist are kept, so
+/// repeating a call changes nothing.
+/// Returns the links between the book and every requested author
 pub fn link_book_to_authors(
     conn: &mut PgConnection,
     book_id: i32,
     authors_ids: &[i32],
 ) -> Result<Vec<BookAuthor>> {
-    // First, verify all authors exist
-    let existing_authors = authors::table
-        .filter(authors::id.eq_any(authors_ids))
-        .select(Author::as_select())
-        .load(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to verify authors: {}", e))?;
-
-    if existing_authors.len() != authors_ids.len() {
-        let existing_ids: Vec<i32> = existing_authors.iter().map(|a| a.id).collect();
-        let missing_ids: Vec<i32> = authors_ids
+    link_authors(conn, book_id, authors_ids, "authors_ids")
+}
+
+/// [`link_book_to_authors`], reporting invalid authors under `field`, e.g.
+/// `field[2]` for an unknown third author
+fn link_authors(
+    conn: &mut PgConnection,
+    book_id: i32,
+    requested_ids: &[i32],
+    field: &str,
+) -> Result<Vec<BookAuthor>> {
+    let mut authors_ids = requested_ids.to_vec();
+    authors_ids.sort_unstable();
+    authors_ids.dedup();
+
+    conn.transaction(|conn| {
+        let publication_year = books::table
+            .find(book_id)
+            .filter(books::deleted_at.is_null())
+            .select(books::publication_year)
+            .first::<i32>(conn)
+            .optional()
+            .with_context(|| format!("Failed to query book {}", book_id))?
+            .ok_or_else(|| Error::not_found("Book", book_id))?;
+
+        // First, verify all authors exist. Shared locks keep them from being
+        // deleted until the links are stored (see `delete_author`)
+        let existing_authors = authors::table
+            .filter(authors::id.eq_any(&authors_ids))
+            .filter(authors::deleted_at.is_null())
+            .for_share()
+            .select(Author::as_select())
+            .load(conn)
+            .context("Failed to verify authors")?;
+
+        let mut validator = Validator::new();
+        for (i, id) in requested_ids.iter().enumerate() {
+            if !existing_authors.iter().any(|author| author.id == *id) {
+                validator = validator.fail(
+                    &format!("{}[{}]", field, i),
+                    format!("no author with ID {}", id),
+                );
+            }
+        }
+        validator
+            .published_after_births(field, publication_year, &existing_authors)
+            .finish()?;
+
+        let new_links: Vec<NewBookAuthor> = authors_ids
             .iter()
-            .filter(|&id| !existing_ids.contains(id))
-            .cloned()
+            .map(|&author_id| NewBookAuthor::new(book_id, author_id))
             .collect();
-        return Err(anyhow::anyhow!(
-            "Authors with IDs {:?} not found",
-            missing_ids
-        ));
-    }
+        diesel::insert_into(books_authors::table)
+            .values(&new_links)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .context("Failed to link book and authors")?;
 
-    // Create BookAuthor relationships for each author
-    let mut book_authors = Vec::new();
-    for &author_id in authors_ids {
-        let book_author = link_book_author(conn, book_id, author_id)?;
-        book_authors.push(book_author);
-    }
+        books_authors::table
+            .filter(books_authors::book_id.eq(book_id))
+            .filter(books_authors::author_id.eq_any(&authors_ids))
+            .order(books_authors::author_id)
+            .select(BookAuthor::as_select())
+            .load(conn)
+            .context("Failed to load book links")
+    })
+}
 
-    Ok(book_authors)
+/// An author of a book being created
+#[derive(Debug)]
+pub enum AuthorInput {
+    /// An author already stored, by ID
+    Existing(i32),
+    /// An author to store along with the book
+    New(NewAuthor),
+}
+
+/// Creates a book together with its authors in one transaction: new
+/// authors are inserted, then the book is linked to every author with
+/// [`link_book_to_authors`]. When any step fails nothing is stored; unknown
+/// authors are reported as `authors[i]`.
+pub fn insert_book_with_authors(
+    conn: &mut PgConnection,
+    new_book: &NewBook,
+    authors: &[AuthorInput],
+) -> Result<BookOutput> {
+    conn.transaction(|conn| {
+        let book = insert_book(conn, new_book)?;
+
+        let mut authors_ids = Vec::with_capacity(authors.len());
+        for author in authors {
+            authors_ids.push(match author {
+                AuthorInput::Existing(id) => *id,
+                AuthorInput::New(new_author) => insert_author(conn, new_author)?.id,
+            });
+        }
+        link_authors(conn, book.id, &authors_ids, "authors")?;
+
+        Ok(with_authors(conn, vec![book])?.remove(0))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::database::initialization::establish_connection;
+    use crate::embedding::HashingEmbedder;
     use crate::models::NewBook;
 
     // Helper function to check if database is available
@@ -122,8 +200,13 @@ mod tests {
         }
 
         let mut conn = establish_connection().expect("Failed to connect to database");
-        let new_book =
-            NewBook::new("Test Book", 2025, "A test book summary").expect("Failed to create book");
+        let new_book = NewBook::new(
+            "Test Book",
+            2025,
+            "A test book summary",
+            &HashingEmbedder::new(),
+        )
+        .expect("Failed to create book");
 
         let result = insert_book(&mut conn, &new_book);
         assert!(result.is_ok());
@@ -156,7 +239,13 @@ mod tests {
             .expect("Failed to insert author2");
 
         // Insert a book
-        let new_book = NewBook::new("Book With Two Authors", 2024, "Summary").unwrap();
+        let new_book = NewBook::new(
+            "Book With Two Authors",
+            2024,
+            "Summary",
+            &HashingEmbedder::new(),
+        )
+        .unwrap();
         let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
 
         // Link book to both authors
@@ -182,4 +271,154 @@ mod tests {
             .execute(&mut conn)
             .ok();
     }
+
+    #[test]
+    fn test_link_rejects_author_born_after_publication() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let author = insert_author(&mut conn, &NewAuthor::new("Unborn Author", 1990, "Brazil"))
+            .expect("Failed to insert author");
+        let new_book = NewBook::new(
+            "Book Older Than Its Author",
+            1950,
+            "Summary",
+            &HashingEmbedder::new(),
+        )
+        .unwrap();
+        let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+
+        match link_book_to_authors(&mut conn, book.id, &[author.id]) {
+            Err(Error::InvalidFields(errors)) => assert_eq!(errors[0].field, "authors_ids"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(matches!(
+            link_book_to_authors(&mut conn, -1, &[author.id]),
+            Err(Error::NotFound(_))
+        ));
+        match link_book_to_authors(&mut conn, book.id, &[author.id, -1]) {
+            Err(Error::InvalidFields(errors)) => {
+                assert_eq!(errors[0].field, "authors_ids[1]");
+                assert_eq!(errors[1].field, "authors_ids");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // Cleanup
+        diesel::delete(books::table.filter(books::id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(authors::table.filter(authors::id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_link_book_to_authors_is_idempotent() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let author1 = insert_author(&mut conn, &NewAuthor::new("Idempotent One", 1950, "Chile"))
+            .expect("Failed to insert author1");
+        let author2 = insert_author(&mut conn, &NewAuthor::new("Idempotent Two", 1960, "Peru"))
+            .expect("Failed to insert author2");
+        let new_book =
+            NewBook::new("Idempotent Book", 2000, "Summary", &HashingEmbedder::new()).unwrap();
+        let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+
+        // Repeated IDs are linked once
+        let links = link_book_to_authors(&mut conn, book.id, &[author2.id, author1.id, author2.id])
+            .expect("Failed to link authors");
+        assert_eq!(links.len(), 2);
+
+        // Linking again keeps the existing links
+        let links = link_book_to_authors(&mut conn, book.id, &[author1.id, author2.id])
+            .expect("Failed to link authors again");
+        assert_eq!(links.len(), 2);
+        let stored: i64 = books_authors::table
+            .filter(books_authors::book_id.eq(book.id))
+            .count()
+            .get_result(&mut conn)
+            .unwrap();
+        assert_eq!(stored, 2);
+
+        // Cleanup
+        diesel::delete(books_authors::table.filter(books_authors::book_id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(books::table.filter(books::id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(authors::table.filter(authors::id.eq_any([author1.id, author2.id])))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_insert_book_with_authors() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let existing = insert_author(&mut conn, &NewAuthor::new("Atomic Existing", 1900, "Cuba"))
+            .expect("Failed to insert author");
+        let embedder = HashingEmbedder::new();
+
+        let new_book = NewBook::new("Atomic Book", 1990, "Summary", &embedder).unwrap();
+        let book = insert_book_with_authors(
+            &mut conn,
+            &new_book,
+            &[
+                AuthorInput::Existing(existing.id),
+                AuthorInput::New(NewAuthor::new("Atomic New", 1950, "Cuba")),
+            ],
+        )
+        .expect("Failed to insert book with authors");
+        let names: Vec<&str> = book.authors.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["Atomic Existing", "Atomic New"]);
+
+        // A missing author rolls back the book and the new author
+        let new_book = NewBook::new("Atomic Rolled Back", 1990, "Summary", &embedder).unwrap();
+        let result = insert_book_with_authors(
+            &mut conn,
+            &new_book,
+            &[
+                AuthorInput::New(NewAuthor::new("Atomic Rolled Back Author", 1950, "Cuba")),
+                AuthorInput::Existing(-1),
+            ],
+        );
+        match result {
+            Err(Error::InvalidFields(errors)) => assert_eq!(errors[0].field, "authors[1]"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let leftovers: i64 = books::table
+            .filter(books::title.eq("Atomic Rolled Back"))
+            .count()
+            .get_result(&mut conn)
+            .unwrap();
+        assert_eq!(leftovers, 0);
+        let leftovers: i64 = authors::table
+            .filter(authors::name.eq("Atomic Rolled Back Author"))
+            .count()
+            .get_result(&mut conn)
+            .unwrap();
+        assert_eq!(leftovers, 0);
+
+        // Cleanup
+        let authors_ids: Vec<i32> = book.authors.iter().map(|a| a.id).collect();
+        diesel::delete(books_authors::table.filter(books_authors::book_id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(books::table.filter(books::id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(authors::table.filter(authors::id.eq_any(authors_ids)))
+            .execute(&mut conn)
+            .ok();
+    }
 }
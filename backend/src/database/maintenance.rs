@@ -0,0 +1,217 @@
+use anyhow::Result;
+use diesel::prelude::*;
+use pgvector::Vector;
+
+use crate::embedding::vector::norm;
+use crate::embedding::{Embedder, TfidfEmbedder, TfidfOptions};
+use crate::schema::books;
+
+/// Number of books re-embedded per batch
+const REEMBED_BATCH_SIZE: i64 = 100;
+
+/// Counts books, not deleted, whose stored embedding was not produced by
+/// `embedder`
+pub fn count_stale_embeddings(conn: &mut PgConnection, embedder: &dyn Embedder) -> Result<i64> {
+    books::table
+        .filter(books::deleted_at.is_null())
+        .filter(books::embedding_version.is_distinct_from(embedder.version()))
+        .count()
+        .get_result(conn)
+        .map_err(|e| anyhow::anyhow!("Failed to count stale embeddings: {}", e))
+}
+
+/// Outcome of [`reembed_stale_books`]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ReembedReport {
+    /// Books whose embedding was recomputed
+    pub updated: usize,
+    /// Of those, books whose abstract embeds to a zero vector, e.g. when no
+    /// word of it is in a TF-IDF vocabulary. They are stored without an
+    /// embedding, as a zero vector has no cosine distance to anything, and
+    /// stay out of vector search.
+    pub without_embedding: usize,
+}
+
+/// Recomputes the embedding of every book, not deleted, whose stored vector
+/// was produced by a different embedder version. Deleted books are embedded
+/// again when restored.
+pub fn reembed_stale_books(
+    conn: &mut PgConnection,
+    embedder: &dyn Embedder,
+) -> Result<ReembedReport> {
+    let mut report = ReembedReport::default();
+
+    loop {
+        let batch: Vec<(i32, String)> = books::table
+            .filter(books::deleted_at.is_null())
+            .filter(books::embedding_version.is_distinct_from(embedder.version()))
+            .order(books::id)
+            .limit(REEMBED_BATCH_SIZE)
+            .select((books::id, books::abstract_text))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load books to re-embed: {}", e))?;
+        if batch.is_empty() {
+            break;
+        }
+
+        let texts: Vec<&str> = batch.iter().map(|(_, text)| text.as_str()).collect();
+        let embeddings: Vec<Option<Vector>> = embedder
+            .embed_batch(&texts)?
+            .into_iter()
+            .map(|embedding| (norm(&embedding) > 0.0).then(|| Vector::from(embedding)))
+            .collect();
+
+        conn.transaction(|conn| {
+            for ((book_id, _), embedding) in batch.iter().zip(&embeddings) {
+                // The version is stored even without an embedding, so the
+                // book is not picked up again
+                diesel::update(books::table.find(book_id))
+                    .set((
+                        books::embedding.eq(embedding),
+                        books::embedding_version.eq(embedder.version()),
+                    ))
+                    .execute(conn)?;
+            }
+            diesel::result::QueryResult::Ok(())
+        })
+        .map_err(|e| anyhow::anyhow!("Failed to store re-computed embeddings: {}", e))?;
+
+        report.updated += batch.len();
+        report.without_embedding += embeddings.iter().filter(|e| e.is_none()).count();
+    }
+
+    Ok(report)
+}
+
+/// Fits a TF-IDF embedder over the abstracts of every book not deleted
+pub fn fit_tfidf(conn: &mut PgConnection, options: TfidfOptions) -> Result<TfidfEmbedder> {
+    let abstracts: Vec<String> = books::table
+        .filter(books::deleted_at.is_null())
+        .order(books::id)
+        .select(books::abstract_text)
+        .load(conn)
+        .map_err(|e| anyhow::anyhow!("Failed to load book abstracts: {}", e))?;
+
+    let documents: Vec<&str> = abstracts.iter().map(String::as_str).collect();
+    TfidfEmbedder::fit(&documents, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::initialization::establish_connection;
+    use crate::database::insertion::insert_book;
+    use crate::embedding::HashingEmbedder;
+    use crate::models::NewBook;
+
+    // Helper function to check if database is available
+    fn db_available() -> bool {
+        std::env::var("DATABASE_URL").is_ok()
+    }
+
+    #[test]
+    fn test_reembed_stale_books() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+
+        let mut new_book = NewBook::new(
+            "Stale Embedding Test Book",
+            2025,
+            "A book whose vector came from an older embedder",
+            &embedder,
+        )
+        .expect("Failed to create book");
+        new_book.embedding_version = Some("obsolete-v0".to_string());
+        let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+
+        // Deleted books are left alone
+        let mut deleted_book = NewBook::new(
+            "Deleted Stale Embedding Test Book",
+            2025,
+            "A deleted book whose vector came from an older embedder",
+            &embedder,
+        )
+        .expect("Failed to create book");
+        deleted_book.embedding_version = Some("obsolete-v0".to_string());
+        let deleted_book = insert_book(&mut conn, &deleted_book).expect("Failed to insert book");
+        diesel::update(books::table.find(deleted_book.id))
+            .set(books::deleted_at.eq(diesel::dsl::now))
+            .execute(&mut conn)
+            .unwrap();
+
+        assert!(count_stale_embeddings(&mut conn, &embedder).unwrap() >= 1);
+        let report = reembed_stale_books(&mut conn, &embedder).expect("Failed to re-embed books");
+        assert!(report.updated >= 1);
+        assert_eq!(count_stale_embeddings(&mut conn, &embedder).unwrap(), 0);
+        let deleted_version: Option<String> = books::table
+            .find(deleted_book.id)
+            .select(books::embedding_version)
+            .first(&mut conn)
+            .unwrap();
+        assert_eq!(deleted_version.as_deref(), Some("obsolete-v0"));
+
+        let version: Option<String> = books::table
+            .find(book.id)
+            .select(books::embedding_version)
+            .first(&mut conn)
+            .unwrap();
+        assert_eq!(version.as_deref(), Some(embedder.version()));
+
+        // Cleanup
+        diesel::delete(books::table.filter(books::id.eq_any([book.id, deleted_book.id])))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_reembed_stores_no_zero_vectors() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+        let mut new_book = NewBook::new(
+            "Zero Vector Test Book",
+            2025,
+            "An abstract that a vocabulary may not cover",
+            &embedder,
+        )
+        .expect("Failed to create book");
+        new_book.embedding_version = Some("obsolete-v0".to_string());
+        let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+
+        // Nothing in the abstract is in the vocabulary of this model
+        let tfidf = TfidfEmbedder::fit(&["xyzzy"], TfidfOptions::default()).unwrap();
+        let report = reembed_stale_books(&mut conn, &tfidf).expect("Failed to re-embed books");
+        assert!(report.without_embedding >= 1);
+        let (embedding, version): (Option<Vector>, Option<String>) = books::table
+            .find(book.id)
+            .select((books::embedding, books::embedding_version))
+            .first(&mut conn)
+            .unwrap();
+        assert!(embedding.is_none());
+        assert_eq!(version.as_deref(), Some(tfidf.version()));
+
+        // Cleanup
+        diesel::delete(books::table.filter(books::id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_fit_tfidf() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = fit_tfidf(&mut conn, TfidfOptions::default()).expect("Failed to fit");
+        assert!(embedder.vocabulary_size() <= embedder.dimension());
+        assert_eq!(embedder.embed("qualquer texto").unwrap().len(), 512);
+    }
+}
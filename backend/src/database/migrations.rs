@@ -0,0 +1,282 @@
+use anyhow::Result;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Bool, Integer, Nullable, Text};
+
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+
+use crate::embedding::Embedder;
+
+/// Migrations from the `migrations` directory, compiled into the binary
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Oldest pgvector release with iterative HNSW scans (`hnsw.iterative_scan`),
+/// which filtered vector search relies on
+const MIN_VECTOR_VERSION: (u32, u32) = (0, 8);
+
+/// Whether a single embedded migration has been applied to the database
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationStatus {
+    pub version: String,
+    pub applied: bool,
+}
+
+/// Applies every pending migration and returns the versions that were run
+pub fn run_pending_migrations(conn: &mut PgConnection) -> Result<Vec<String>> {
+    let versions = conn
+        .run_pending_migrations(MIGRATIONS)
+        .map_err(|e| anyhow::anyhow!("Failed to run pending migrations: {}", e))?;
+
+    Ok(versions.iter().map(|v| v.to_string()).collect())
+}
+
+/// Reverts the most recently applied migration and returns its version
+pub fn revert_last_migration(conn: &mut PgConnection) -> Result<String> {
+    let version = conn
+        .revert_last_migration(MIGRATIONS)
+        .map_err(|e| anyhow::anyhow!("Failed to revert last migration: {}", e))?;
+
+    Ok(version.to_string())
+}
+
+/// Lists every embedded migration together with whether it has been applied
+pub fn migration_status(conn: &mut PgConnection) -> Result<Vec<MigrationStatus>> {
+    let pending: Vec<String> = conn
+        .pending_migrations(MIGRATIONS)
+        .map_err(|e| anyhow::anyhow!("Failed to list pending migrations: {}", e))?
+        .iter()
+        .map(|m| m.name().version().to_string())
+        .collect();
+    let applied: Vec<String> = conn
+        .applied_migrations()
+        .map_err(|e| anyhow::anyhow!("Failed to list applied migrations: {}", e))?
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+
+    let mut status: Vec<MigrationStatus> = applied
+        .into_iter()
+        .map(|version| MigrationStatus {
+            version,
+            applied: true,
+        })
+        .chain(pending.into_iter().map(|version| MigrationStatus {
+            version,
+            applied: false,
+        }))
+        .collect();
+    status.sort_by(|a, b| a.version.cmp(&b.version));
+
+    Ok(status)
+}
+
+#[derive(QueryableByName)]
+struct Exists {
+    #[diesel(sql_type = Bool)]
+    found: bool,
+}
+
+#[derive(QueryableByName)]
+struct ExtensionVersion {
+    #[diesel(sql_type = Nullable<Text>)]
+    version: Option<String>,
+}
+
+/// Whether the `major.minor[.patch]` version `version` is at least `minimum`
+fn version_at_least(version: &str, minimum: (u32, u32)) -> bool {
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+    match (parts.next().flatten(), parts.next().flatten()) {
+        (Some(major), Some(minor)) => (major, minor) >= minimum,
+        _ => false,
+    }
+}
+
+/// Checks that the database is ready to serve requests: every embedded
+/// migration has been applied and a recent enough `vector` extension is
+/// installed
+pub fn verify_schema(conn: &mut PgConnection) -> Result<()> {
+    let pending = conn
+        .pending_migrations(MIGRATIONS)
+        .map_err(|e| anyhow::anyhow!("Failed to list pending migrations: {}", e))?;
+    if !pending.is_empty() {
+        let versions: Vec<String> = pending
+            .iter()
+            .map(|m| m.name().version().to_string())
+            .collect();
+        return Err(anyhow::anyhow!(
+            "{} pending migration(s): {}. Run with --migrate or `migrate up` first",
+            versions.len(),
+            versions.join(", ")
+        ));
+    }
+
+    let extension = diesel::sql_query(
+        "SELECT (SELECT extversion FROM pg_extension WHERE extname = 'vector') AS version",
+    )
+    .get_result::<ExtensionVersion>(conn)
+    .map_err(|e| anyhow::anyhow!("Failed to check for the vector extension: {}", e))?;
+    let Some(version) = extension.version else {
+        return Err(anyhow::anyhow!(
+            "The `vector` extension is not installed in this database"
+        ));
+    };
+    if !version_at_least(&version, MIN_VECTOR_VERSION) {
+        return Err(anyhow::anyhow!(
+            "The `vector` extension is at version {}, but {}.{} or later is required. \
+             Upgrade pgvector and run `ALTER EXTENSION vector UPDATE`",
+            version,
+            MIN_VECTOR_VERSION.0,
+            MIN_VECTOR_VERSION.1
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(QueryableByName)]
+struct Count {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+/// Checks that vector search can serve `embedder`: an HNSW index covers
+/// its dimension, and the vectors stored by its version all have that
+/// dimension. `books.embedding` itself accepts any dimension, so nothing
+/// else stops a misconfigured embedder.
+pub fn verify_embedding_dimension(conn: &mut PgConnection, embedder: &dyn Embedder) -> Result<()> {
+    let dimension = embedder.dimension();
+    if !has_embedding_index(conn, dimension)? {
+        return Err(anyhow::anyhow!(
+            "No HNSW index covers {}-dimensional embeddings, so vector search would scan \
+             every book. Add a migration creating book_embedding_{}_cosine_index, or use \
+             an embedder of 384, 512 or 768 dimensions",
+            dimension,
+            dimension
+        ));
+    }
+
+    let mismatched = diesel::sql_query(
+        "SELECT COUNT(*) AS count FROM books \
+         WHERE embedding_version = $1 AND vector_dims(embedding) <> $2",
+    )
+    .bind::<Text, _>(embedder.version())
+    .bind::<Integer, _>(dimension as i32)
+    .get_result::<Count>(conn)
+    .map_err(|e| anyhow::anyhow!("Failed to check stored embedding dimensions: {}", e))?;
+    if mismatched.count > 0 {
+        return Err(anyhow::anyhow!(
+            "{} book(s) store {} embeddings that are not {}-dimensional; the embedder \
+             configuration changed without changing its version",
+            mismatched.count,
+            embedder.version(),
+            dimension
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether an HNSW index exists for vectors of `dimension` elements. Without
+/// one, vector search still works but scans every book.
+pub fn has_embedding_index(conn: &mut PgConnection, dimension: usize) -> Result<bool> {
+    let index = diesel::sql_query(
+        "SELECT EXISTS (SELECT 1 FROM pg_indexes \
+         WHERE tablename = 'books' AND indexname = $1) AS found",
+    )
+    .bind::<diesel::sql_types::Text, _>(format!("book_embedding_{}_cosine_index", dimension))
+    .get_result::<Exists>(conn)
+    .map_err(|e| anyhow::anyhow!("Failed to check for the embedding index: {}", e))?;
+
+    Ok(index.found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::initialization::establish_connection;
+    use crate::database::insertion::insert_book;
+    use crate::embedding::{HashingEmbedder, HashingOptions};
+    use crate::models::NewBook;
+    use crate::schema::books;
+    use diesel::migration::MigrationSource;
+
+    // Helper function to check if database is available
+    fn db_available() -> bool {
+        std::env::var("DATABASE_URL").is_ok()
+    }
+
+    #[test]
+    fn test_migrations_are_embedded() {
+        let migrations = MigrationSource::<diesel::pg::Pg>::migrations(&MIGRATIONS)
+            .expect("Failed to read embedded migrations");
+        assert!(!migrations.is_empty());
+    }
+
+    #[test]
+    fn test_version_at_least() {
+        assert!(version_at_least("0.8.0", (0, 8)));
+        assert!(version_at_least("0.10.1", (0, 8)));
+        assert!(version_at_least("1.0", (0, 8)));
+        assert!(!version_at_least("0.7.4", (0, 8)));
+        assert!(!version_at_least("dev", (0, 8)));
+    }
+
+    #[test]
+    fn test_migration_status() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedded = MigrationSource::<diesel::pg::Pg>::migrations(&MIGRATIONS).unwrap();
+
+        let status = migration_status(&mut conn).expect("Failed to read migration status");
+        assert!(status.len() >= embedded.len());
+    }
+
+    #[test]
+    fn test_verify_embedding_dimension() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = |dimension| {
+            HashingEmbedder::with_options(HashingOptions {
+                dimension,
+                ..HashingOptions::default()
+            })
+            .unwrap()
+        };
+        assert!(verify_embedding_dimension(&mut conn, &embedder(512)).is_ok());
+        // No index covers 3 dimensions
+        assert!(verify_embedding_dimension(&mut conn, &embedder(3)).is_err());
+
+        // A 512-dimensional vector stored under a 384-dimensional version
+        let mut new_book = NewBook::new(
+            "Mismatched Dimension Book",
+            2025,
+            "A vector of the wrong size",
+            &embedder(512),
+        )
+        .unwrap();
+        new_book.embedding_version = Some(embedder(384).version().to_string());
+        let book = insert_book(&mut conn, &new_book).unwrap();
+        assert!(verify_embedding_dimension(&mut conn, &embedder(384)).is_err());
+
+        // Cleanup
+        diesel::delete(books::table.find(book.id))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_has_embedding_index() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        assert!(has_embedding_index(&mut conn, 512).unwrap());
+        assert!(!has_embedding_index(&mut conn, 3).unwrap());
+    }
+}
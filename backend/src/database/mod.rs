@@ -1,3 +1,11 @@
+pub mod executor;
+pub mod history;
 pub mod initialization;
 pub mod insertion;
-pub mod query;
\ No newline at end of file
+pub mod maintenance;
+pub mod migrations;
+pub mod modification;
+pub mod pagination;
+pub mod pool;
+pub mod query;
+pub mod search;
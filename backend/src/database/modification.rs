@@ -0,0 +1,360 @@
+use diesel::prelude::*;
+
+use crate::database::query::{AuthorOutput, BookOutput, with_authors};
+use crate::error::{Error, Result, ResultExt};
+use crate::models::{Author, AuthorChanges, Book, BookAuthor, BookChanges};
+use crate::schema::{authors, books, books_authors};
+use crate::validation::Validator;
+
+/// Fails when a live book by the author came out before `birth_year`
+pub(crate) fn check_birth_year(
+    conn: &mut PgConnection,
+    author_id: i32,
+    birth_year: i32,
+) -> Result<()> {
+    let first_publication: Option<i32> = books_authors::table
+        .inner_join(books::table)
+        .filter(books_authors::author_id.eq(author_id))
+        .filter(books::deleted_at.is_null())
+        .select(diesel::dsl::min(books::publication_year))
+        .get_result(conn)
+        .with_context(|| format!("Failed to query books of author {}", author_id))?;
+    if let Some(year) = first_publication
+        && birth_year > year
+    {
+        Validator::new()
+            .fail(
+                "birth_year",
+                format!(
+                    "must not be after {}, when a book by the author came out",
+                    year
+                ),
+            )
+            .finish()?;
+    }
+    Ok(())
+}
+
+/// Fails when an author of the book was born after `publication_year`
+pub(crate) fn check_publication_year(
+    conn: &mut PgConnection,
+    book_id: i32,
+    publication_year: i32,
+) -> Result<()> {
+    let authors = authors::table
+        .inner_join(books_authors::table)
+        .filter(books_authors::book_id.eq(book_id))
+        .select(Author::as_select())
+        .load(conn)
+        .with_context(|| format!("Failed to query authors of book {}", book_id))?;
+    Validator::new()
+        .published_after_births("publication_year", publication_year, &authors)
+        .finish()
+}
+
+/// Changes the given fields of an author and returns it. The author cannot
+/// be made younger than any of their books.
+pub fn update_author(
+    conn: &mut PgConnection,
+    author_id: i32,
+    changes: &AuthorChanges,
+) -> Result<AuthorOutput> {
+    conn.transaction(|conn| {
+        let author = authors::table
+            .find(author_id)
+            .filter(authors::deleted_at.is_null())
+            .for_update()
+            .select(Author::as_select())
+            .first(conn)
+            .optional()
+            .with_context(|| format!("Failed to query author {}", author_id))?
+            .ok_or_else(|| Error::not_found("Author", author_id))?;
+
+        if let Some(birth_year) = changes.birth_year {
+            check_birth_year(conn, author_id, birth_year)?;
+        }
+
+        if changes.is_empty() {
+            return Ok(AuthorOutput::from(author));
+        }
+        let author = diesel::update(authors::table.find(author_id))
+            .set(changes)
+            .returning(Author::as_returning())
+            .get_result(conn)
+            .with_context(|| format!("Failed to update author {}", author_id))?;
+        Ok(AuthorOutput::from(author))
+    })
+}
+
+/// Changes the given fields of a book and returns it with its authors. The
+/// book cannot be dated before the birth of any of its authors.
+pub fn update_book(
+    conn: &mut PgConnection,
+    book_id: i32,
+    changes: &BookChanges,
+) -> Result<BookOutput> {
+    conn.transaction(|conn| {
+        let book = books::table
+            .find(book_id)
+            .filter(books::deleted_at.is_null())
+            .for_update()
+            .select(Book::as_select())
+            .first(conn)
+            .optional()
+            .with_context(|| format!("Failed to query book {}", book_id))?
+            .ok_or_else(|| Error::not_found("Book", book_id))?;
+
+        if let Some(publication_year) = changes.publication_year {
+            check_publication_year(conn, book_id, publication_year)?;
+        }
+
+        let book = if changes.is_empty() {
+            book
+        } else {
+            diesel::update(books::table.find(book_id))
+                .set(changes)
+                .returning(Book::as_returning())
+                .get_result(conn)
+                .with_context(|| format!("Failed to update book {}", book_id))?
+        };
+        Ok(with_authors(conn, vec![book])?.remove(0))
+    })
+}
+
+/// Marks an author as deleted and returns it. Refused while books that are
+/// not deleted still link to the author.
+pub fn delete_author(conn: &mut PgConnection, author_id: i32) -> Result<AuthorOutput> {
+    conn.transaction(|conn| {
+        // Locked before looking for books, so a concurrent link either
+        // lands before the check or finds the author deleted
+        authors::table
+            .find(author_id)
+            .filter(authors::deleted_at.is_null())
+            .for_update()
+            .select(authors::id)
+            .first::<i32>(conn)
+            .optional()
+            .with_context(|| format!("Failed to query author {}", author_id))?
+            .ok_or_else(|| Error::not_found("Author", author_id))?;
+
+        let has_books: bool = diesel::select(diesel::dsl::exists(
+            books_authors::table
+                .inner_join(books::table)
+                .filter(books_authors::author_id.eq(author_id))
+                .filter(books::deleted_at.is_null()),
+        ))
+        .get_result(conn)
+        .with_context(|| format!("Failed to query books of author {}", author_id))?;
+        if has_books {
+            return Err(Error::Conflict(format!(
+                "Author with ID {} still has books; delete them or unlink the author first",
+                author_id
+            )));
+        }
+
+        let author = diesel::update(authors::table.find(author_id))
+            .set(authors::deleted_at.eq(diesel::dsl::now))
+            .returning(Author::as_returning())
+            .get_result(conn)
+            .with_context(|| format!("Failed to delete author {}", author_id))?;
+        Ok(AuthorOutput::from(author))
+    })
+}
+
+/// Marks a book as deleted and returns it. Its links to authors are kept,
+/// so restoring the book brings them back.
+pub fn delete_book(conn: &mut PgConnection, book_id: i32) -> Result<BookOutput> {
+    conn.transaction(|conn| {
+        let book = diesel::update(
+            books::table
+                .find(book_id)
+                .filter(books::deleted_at.is_null()),
+        )
+        .set(books::deleted_at.eq(diesel::dsl::now))
+        .returning(Book::as_returning())
+        .get_result(conn)
+        .optional()
+        .with_context(|| format!("Failed to delete book {}", book_id))?
+        .ok_or_else(|| Error::not_found("Book", book_id))?;
+        Ok(with_authors(conn, vec![book])?.remove(0))
+    })
+}
+
+/// Removes the link between a book and one of its authors
+pub fn unlink_book_author(
+    conn: &mut PgConnection,
+    book_id: i32,
+    author_id: i32,
+) -> Result<BookAuthor> {
+    diesel::delete(books_authors::table.find((book_id, author_id)))
+        .returning(BookAuthor::as_returning())
+        .get_result(conn)
+        .optional()
+        .context("Failed to unlink book and author")?
+        .ok_or_else(|| {
+            Error::NotFound(format!(
+                "Book with ID {} is not linked to author with ID {}",
+                book_id, author_id
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::initialization::establish_connection;
+    use crate::database::insertion::{insert_author, insert_book, link_book_author};
+    use crate::database::query::get_book_by_id;
+    use crate::embedding::{Embedder, HashingEmbedder};
+    use crate::models::{NewAuthor, NewBook};
+
+    // Helper function to check if database is available
+    fn db_available() -> bool {
+        std::env::var("DATABASE_URL").is_ok()
+    }
+
+    #[test]
+    fn test_update_author_and_book() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+        let author = insert_author(&mut conn, &NewAuthor::new("Update Author", 1900, "Chile"))
+            .expect("Failed to insert author");
+        let new_book = NewBook::new("Update Book", 1950, "Old summary", &embedder).unwrap();
+        let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+        link_book_author(&mut conn, book.id, author.id).unwrap();
+
+        let changes = AuthorChanges {
+            country: Some("Uruguay".to_string()),
+            ..AuthorChanges::default()
+        };
+        let updated = update_author(&mut conn, author.id, &changes).unwrap();
+        assert_eq!(updated.country, "Uruguay");
+        assert_eq!(updated.name, "Update Author");
+
+        // The author cannot be born after their book came out, nor the book
+        // come out before its author was born
+        let changes = AuthorChanges {
+            birth_year: Some(1960),
+            ..AuthorChanges::default()
+        };
+        assert!(matches!(
+            update_author(&mut conn, author.id, &changes),
+            Err(Error::InvalidFields(_))
+        ));
+        let changes = BookChanges::new(None, Some(1890), None, &embedder).unwrap();
+        assert!(matches!(
+            update_book(&mut conn, book.id, &changes),
+            Err(Error::InvalidFields(_))
+        ));
+
+        // A new abstract comes with a new embedding
+        let changes = BookChanges::new(
+            Some("Updated Book"),
+            None,
+            Some("A rather different summary"),
+            &embedder,
+        )
+        .unwrap();
+        let updated = update_book(&mut conn, book.id, &changes).unwrap();
+        assert_eq!(updated.title, "Updated Book");
+        assert_eq!(updated.authors[0].id, author.id);
+        let embedding: Option<pgvector::Vector> = books::table
+            .find(book.id)
+            .select(books::embedding)
+            .first(&mut conn)
+            .unwrap();
+        assert_eq!(
+            embedding.unwrap().to_vec(),
+            embedder.embed("A rather different summary").unwrap()
+        );
+
+        assert!(matches!(
+            update_author(&mut conn, -1, &AuthorChanges::default()),
+            Err(Error::NotFound(_))
+        ));
+
+        // Cleanup
+        diesel::delete(books_authors::table.filter(books_authors::book_id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(books::table.filter(books::id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(authors::table.filter(authors::id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_delete_rules() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let author = insert_author(&mut conn, &NewAuthor::new("Delete Author", 1900, "Chile"))
+            .expect("Failed to insert author");
+        let new_book =
+            NewBook::new("Delete Book", 1950, "Summary", &HashingEmbedder::new()).unwrap();
+        let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+        link_book_author(&mut conn, book.id, author.id).unwrap();
+
+        // An author with books cannot be deleted
+        assert!(matches!(
+            delete_author(&mut conn, author.id),
+            Err(Error::Conflict(_))
+        ));
+
+        // Unlinking works once
+        unlink_book_author(&mut conn, book.id, author.id).unwrap();
+        assert!(matches!(
+            unlink_book_author(&mut conn, book.id, author.id),
+            Err(Error::NotFound(_))
+        ));
+
+        // A deleted book is hidden but keeps its links, and no longer
+        // holds back the birth year or the deletion of its author
+        link_book_author(&mut conn, book.id, author.id).unwrap();
+        let deleted = delete_book(&mut conn, book.id).unwrap();
+        assert_eq!(deleted.authors.len(), 1);
+        assert!(matches!(
+            get_book_by_id(&mut conn, book.id),
+            Err(Error::NotFound(_))
+        ));
+        let links: i64 = books_authors::table
+            .filter(books_authors::book_id.eq(book.id))
+            .count()
+            .get_result(&mut conn)
+            .unwrap();
+        assert_eq!(links, 1);
+        let changes = AuthorChanges {
+            birth_year: Some(1960),
+            ..AuthorChanges::default()
+        };
+        let updated = update_author(&mut conn, author.id, &changes).unwrap();
+        assert_eq!(updated.birth_year, 1960);
+
+        let deleted = delete_author(&mut conn, author.id).unwrap();
+        assert_eq!(deleted.id, author.id);
+        assert!(matches!(
+            delete_author(&mut conn, author.id),
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            delete_book(&mut conn, book.id),
+            Err(Error::NotFound(_))
+        ));
+
+        // Cleanup
+        diesel::delete(books::table.filter(books::id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(authors::table.filter(authors::id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+}
@@ -0,0 +1,365 @@
+//! Keyset pagination shared by every list query. A page ends with an opaque
+//! cursor holding the sort key and id of its last row; the next page starts
+//! strictly after that position, so rows inserted or deleted meanwhile never
+//! shift results between pages the way offsets do.
+
+use diesel::expression::{AsExpression, BoxableExpression};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::query_dsl::methods::{FilterDsl, OrderDsl};
+use diesel::sql_types::{Bool, Integer, SingleValue, SqlType, is_nullable};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Field a list is ordered by. Authors sort by name for `Title` and by
+/// birth year for `Year`; ties are always broken by id.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    Title,
+    Year,
+    Id,
+    /// Match score of a search, best first by default
+    Relevance,
+}
+
+impl SortBy {
+    /// Order used when the client asks for none
+    pub fn default_order(self) -> SortOrder {
+        match self {
+            SortBy::Relevance => SortOrder::Desc,
+            _ => SortOrder::Asc,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Which page of a list to load and how the list is ordered
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRequest {
+    pub limit: i64,
+    /// `next_cursor` of the previous page; `None` for the first page
+    pub cursor: Option<String>,
+    pub sort: SortBy,
+    pub order: SortOrder,
+    /// Also count every row of the list, at the cost of an extra query
+    pub with_total: bool,
+}
+
+impl PageRequest {
+    /// The first `limit` rows ordered by `sort` in its default order
+    pub fn first(limit: i64, sort: SortBy) -> Self {
+        PageRequest {
+            limit,
+            cursor: None,
+            sort,
+            order: sort.default_order(),
+            with_total: false,
+        }
+    }
+
+    /// Decodes the cursor, checking it was issued for the same ordering
+    pub(crate) fn position(&self) -> Result<Option<Cursor>> {
+        let Some(encoded) = &self.cursor else {
+            return Ok(None);
+        };
+        let cursor = Cursor::decode(encoded)?;
+        if cursor.sort != self.sort || cursor.order != self.order {
+            return Err(Error::BadRequest(
+                "Cursor was issued for a different sort order".to_string(),
+            ));
+        }
+        Ok(Some(cursor))
+    }
+
+    /// Rows to fetch: one more than the page, to tell whether another follows
+    pub(crate) fn fetch_limit(&self) -> i64 {
+        self.limit + 1
+    }
+}
+
+/// One page of a list
+#[derive(Debug)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    /// Cursor of the following page; `None` on the last page
+    pub next_cursor: Option<String>,
+    /// Rows in the whole list, when requested
+    pub total: Option<i64>,
+}
+
+impl<T> Paginated<T> {
+    /// Builds a page from rows fetched with [`PageRequest::fetch_limit`].
+    /// `position` returns the sort key and id of a row.
+    pub(crate) fn from_rows(
+        mut rows: Vec<T>,
+        page: &PageRequest,
+        total: Option<i64>,
+        position: impl Fn(&T) -> (SortKey, i32),
+    ) -> Self {
+        let next_cursor = if rows.len() as i64 > page.limit {
+            rows.truncate(page.limit as usize);
+            rows.last().map(|row| {
+                let (key, id) = position(row);
+                Cursor {
+                    sort: page.sort,
+                    order: page.order,
+                    key,
+                    id,
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        Paginated {
+            items: rows,
+            next_cursor,
+            total,
+        }
+    }
+
+    /// Converts the whole page of items at once, e.g. to batch a query
+    pub(crate) fn try_map_items<U>(
+        self,
+        f: impl FnOnce(Vec<T>) -> Result<Vec<U>>,
+    ) -> Result<Paginated<U>> {
+        Ok(Paginated {
+            items: f(self.items)?,
+            next_cursor: self.next_cursor,
+            total: self.total,
+        })
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Paginated<U> {
+        Paginated {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total: self.total,
+        }
+    }
+}
+
+/// Returned for a cursor this server did not issue
+fn malformed_cursor() -> Error {
+    Error::BadRequest("Malformed cursor".to_string())
+}
+
+/// Sort key of the last row of a page
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SortKey {
+    Text(String),
+    Int(i32),
+    Float(f32),
+}
+
+impl SortKey {
+    pub(crate) fn into_text(self) -> Result<String> {
+        match self {
+            SortKey::Text(text) => Ok(text),
+            _ => Err(malformed_cursor()),
+        }
+    }
+
+    pub(crate) fn into_int(self) -> Result<i32> {
+        match self {
+            SortKey::Int(value) => Ok(value),
+            _ => Err(malformed_cursor()),
+        }
+    }
+
+    pub(crate) fn into_float(self) -> Result<f32> {
+        match self {
+            SortKey::Float(value) => Ok(value),
+            _ => Err(malformed_cursor()),
+        }
+    }
+}
+
+/// Position right after which a page starts
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct Cursor {
+    sort: SortBy,
+    order: SortOrder,
+    pub(crate) key: SortKey,
+    pub(crate) id: i32,
+}
+
+impl Cursor {
+    /// Hex-encoded JSON, safe to pass around in URLs
+    fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("cursor serializes to JSON");
+        json.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn decode(encoded: &str) -> Result<Self> {
+        if !encoded.len().is_multiple_of(2) || !encoded.is_ascii() {
+            return Err(malformed_cursor());
+        }
+        let bytes = (0..encoded.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&encoded[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| malformed_cursor())?;
+        serde_json::from_slice(&bytes).map_err(|_| malformed_cursor())
+    }
+}
+
+/// The key and id the page starts after, with the key converted by
+/// `into_key` to the type of the sorted column
+pub(crate) fn after<V>(
+    position: Option<Cursor>,
+    into_key: fn(SortKey) -> Result<V>,
+) -> Result<Option<(V, i32)>> {
+    position
+        .map(|cursor| Ok((into_key(cursor.key)?, cursor.id)))
+        .transpose()
+}
+
+/// Fails for lists with no match score to sort by relevance with
+pub(crate) fn no_relevance() -> Error {
+    Error::Validation("This list has no relevance to sort by".to_string())
+}
+
+/// A boxed expression over the rows of `QS`
+pub(crate) type Boxed<'a, QS, ST> = Box<dyn BoxableExpression<QS, Pg, SqlType = ST> + 'a>;
+
+/// Orders `query` by `(key, id)` in `order` and, when `after` holds the
+/// position of the previous page's last row, keeps only rows past it
+pub(crate) fn keyset<'a, Q, QS, ST, V>(
+    query: Q,
+    order: SortOrder,
+    key: impl Fn() -> Boxed<'a, QS, ST>,
+    id: impl Fn() -> Boxed<'a, QS, Integer>,
+    after: Option<(V, i32)>,
+) -> Q
+where
+    QS: 'a,
+    ST: SqlType<IsNull = is_nullable::NotNull> + SingleValue + 'a,
+    V: AsExpression<ST> + Clone,
+    V::Expression: BoxableExpression<QS, Pg, SqlType = ST> + 'a,
+    Q: OrderDsl<
+            (
+                diesel::helper_types::Asc<Boxed<'a, QS, ST>>,
+                diesel::helper_types::Asc<Boxed<'a, QS, Integer>>,
+            ),
+            Output = Q,
+        > + OrderDsl<
+            (
+                diesel::helper_types::Desc<Boxed<'a, QS, ST>>,
+                diesel::helper_types::Desc<Boxed<'a, QS, Integer>>,
+            ),
+            Output = Q,
+        > + FilterDsl<Boxed<'a, QS, Bool>, Output = Q>,
+{
+    let value = |v: &V| -> Boxed<'a, QS, ST> { Box::new(v.clone().as_expression()) };
+    match order {
+        SortOrder::Asc => {
+            let query = OrderDsl::order(query, (key().asc(), id().asc()));
+            match after {
+                Some((v, last_id)) => FilterDsl::filter(
+                    query,
+                    Box::new(
+                        key()
+                            .gt(value(&v))
+                            .or(key().eq(value(&v)).and(id().gt(last_id))),
+                    ) as Boxed<'a, QS, Bool>,
+                ),
+                None => query,
+            }
+        }
+        SortOrder::Desc => {
+            let query = OrderDsl::order(query, (key().desc(), id().desc()));
+            match after {
+                Some((v, last_id)) => FilterDsl::filter(
+                    query,
+                    Box::new(
+                        key()
+                            .lt(value(&v))
+                            .or(key().eq(value(&v)).and(id().lt(last_id))),
+                    ) as Boxed<'a, QS, Bool>,
+                ),
+                None => query,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(sort: SortBy, cursor: Option<String>) -> PageRequest {
+        PageRequest {
+            cursor,
+            ..PageRequest::first(2, sort)
+        }
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor {
+            sort: SortBy::Title,
+            order: SortOrder::Asc,
+            key: SortKey::Text("Dom Casmurro".to_string()),
+            id: 7,
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+    }
+
+    #[test]
+    fn test_malformed_cursor() {
+        for encoded in ["zz", "abc", "7b7d", "ção"] {
+            let error = page(SortBy::Title, Some(encoded.to_string()))
+                .position()
+                .unwrap_err();
+            assert!(matches!(error, Error::BadRequest(_)));
+        }
+    }
+
+    #[test]
+    fn test_cursor_must_match_sort() {
+        let rows = vec![(1, "A"), (2, "B"), (3, "C")];
+        let position = |row: &(i32, &str)| (SortKey::Text(row.1.to_string()), row.0);
+        let first = Paginated::from_rows(rows, &page(SortBy::Title, None), None, position);
+        let next = first.next_cursor.clone();
+
+        assert!(
+            page(SortBy::Title, next.clone())
+                .position()
+                .unwrap()
+                .is_some()
+        );
+        let error = page(SortBy::Year, next).position().unwrap_err();
+        assert!(matches!(error, Error::BadRequest(_)));
+    }
+
+    #[test]
+    fn test_from_rows() {
+        let position = |id: &i32| (SortKey::Int(*id), *id);
+
+        let page_of_two = page(SortBy::Id, None);
+        let first = Paginated::from_rows(vec![1, 2, 3], &page_of_two, Some(5), position);
+        assert_eq!(first.items, vec![1, 2]);
+        assert_eq!(first.total, Some(5));
+        let cursor = page(SortBy::Id, first.next_cursor)
+            .position()
+            .unwrap()
+            .unwrap();
+        assert_eq!((cursor.key, cursor.id), (SortKey::Int(2), 2));
+
+        let last = Paginated::from_rows(vec![4, 5], &page_of_two, None, position);
+        assert_eq!(last.items, vec![4, 5]);
+        assert!(last.next_cursor.is_none());
+    }
+}
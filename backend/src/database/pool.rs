@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool, PooledConnection};
+
+use crate::Config;
+
+/// Shared pool of Postgres connections, created once at startup
+pub type DbPool = Pool<ConnectionManager<PgConnection>>;
+
+/// A connection checked out of the [`DbPool`]
+pub type DbConnection = PooledConnection<ConnectionManager<PgConnection>>;
+
+/// Applies per-connection session settings when the pool opens a new connection
+#[derive(Debug)]
+struct SessionCustomizer {
+    statement_timeout_ms: u64,
+}
+
+impl CustomizeConnection<PgConnection, diesel::r2d2::Error> for SessionCustomizer {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), diesel::r2d2::Error> {
+        // A value of zero disables the timeout, matching Postgres semantics
+        diesel::sql_query(format!(
+            "SET statement_timeout = {}",
+            self.statement_timeout_ms
+        ))
+        .execute(conn)
+        .map(|_| ())
+        .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// Builds the connection pool described by the application configuration.
+/// Fails if no connection can be opened within the checkout timeout.
+pub fn create_pool(config: &Config) -> Result<DbPool> {
+    let manager = ConnectionManager::<PgConnection>::new(&config.database_url);
+
+    Pool::builder()
+        .max_size(config.db_pool_max_size)
+        .connection_timeout(Duration::from_secs(config.db_pool_timeout_secs))
+        .connection_customizer(Box::new(SessionCustomizer {
+            statement_timeout_ms: config.db_statement_timeout_ms,
+        }))
+        .build(manager)
+        .map_err(|e| anyhow::anyhow!("Failed to create database connection pool: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_pool() {
+        // This test requires a valid database connection
+        if std::env::var("DATABASE_URL").is_err() {
+            return;
+        }
+
+        let config = Config::from_env().expect("Failed to load configuration");
+        let pool = create_pool(&config).expect("Failed to create pool");
+        let mut conn = pool.get().expect("Failed to check out connection");
+
+        #[derive(QueryableByName)]
+        struct Setting {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            statement_timeout: String,
+        }
+
+        let setting = diesel::sql_query("SHOW statement_timeout")
+            .get_result::<Setting>(&mut conn)
+            .expect("Failed to read statement_timeout");
+        assert!(!setting.statement_timeout.is_empty());
+    }
+}
@@ -1,10 +1,21 @@
-use anyhow::Result;
+use chrono::{DateTime, Utc};
+use diesel::dsl::sql;
+use diesel::expression::SqlLiteral;
+use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::sql_types::{Bool, Float4, Integer, Nullable, Text};
+use pgvector::sql_types::Vector as VectorType;
 use pgvector::{Vector, VectorExpressionMethods};
 use serde::Serialize;
 
-use crate::embedding::compute_embedding;
-use crate::models::{Author, Book};
+use crate::database::pagination::{
+    Boxed, PageRequest, Paginated, SortBy, SortKey, after, keyset, no_relevance,
+};
+use crate::database::search::{SearchFilters, apply_filters};
+use crate::embedding::Embedder;
+use crate::embedding::vector::norm;
+use crate::error::{Error, Result, ResultExt};
+use crate::models::{Author, AuthorSummary, Book, BookAuthor};
 use crate::schema::{authors, books, books_authors};
 
 #[derive(Serialize, Debug)]
@@ -13,6 +24,9 @@ pub struct AuthorOutput {
     pub name: String,
     pub birth_year: i32,
     pub country: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl From<Author> for AuthorOutput {
@@ -22,6 +36,9 @@ impl From<Author> for AuthorOutput {
             name: author.name,
             birth_year: author.birth_year,
             country: author.country,
+            created_at: author.created_at,
+            updated_at: author.updated_at,
+            deleted_at: author.deleted_at,
         }
     }
 }
@@ -32,145 +49,647 @@ pub struct BookOutput {
     pub title: String,
     pub publication_year: i32,
     pub abstract_text: String,
+    /// Who wrote the book, ordered by name
+    pub authors: Vec<AuthorSummary>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
-impl From<Book> for BookOutput {
-    fn from(book: Book) -> Self {
+impl BookOutput {
+    pub fn new(book: Book, authors: Vec<AuthorSummary>) -> Self {
         BookOutput {
             id: book.id,
             title: book.title,
             publication_year: book.publication_year,
             abstract_text: book.abstract_text,
+            authors,
+            created_at: book.created_at,
+            updated_at: book.updated_at,
+            deleted_at: book.deleted_at,
         }
     }
 }
 
-/// Retrieves authors by partial name match
-pub fn get_authors_by_name(conn: &mut PgConnection, name: &str) -> Result<Vec<AuthorOutput>> {
-    let pattern = format!("%{}%", name);
+/// Authors that are not deleted. Queries start from here, so deleted
+/// authors stay hidden unless one asks for their history.
+pub(crate) fn live_authors<'a>() -> authors::BoxedQuery<'a, Pg> {
+    authors::table
+        .filter(authors::deleted_at.is_null())
+        .into_boxed()
+}
 
-    let authors = authors::table
-        .filter(authors::name.ilike(&pattern))
+/// Books that are not deleted; see [`live_authors`]
+pub(crate) fn live_books<'a>() -> books::BoxedQuery<'a, Pg> {
+    books::table
+        .filter(books::deleted_at.is_null())
+        .into_boxed()
+}
+
+/// Loads the authors of every book in `books` with a single query, grouped
+/// in the order of `books`
+pub(crate) fn load_authors(
+    conn: &mut PgConnection,
+    books: &[Book],
+) -> Result<Vec<Vec<AuthorSummary>>> {
+    let links: Vec<(BookAuthor, AuthorSummary)> = BookAuthor::belonging_to(books)
+        .inner_join(authors::table)
+        .filter(authors::deleted_at.is_null())
+        .order((authors::name, authors::id))
+        .select((BookAuthor::as_select(), AuthorSummary::as_select()))
+        .load(conn)
+        .context("Failed to query authors of books")?;
+
+    Ok(links
+        .grouped_by(books)
+        .into_iter()
+        .map(|links| links.into_iter().map(|(_, author)| author).collect())
+        .collect())
+}
+
+/// Turns `books` into outputs carrying their authors
+pub(crate) fn with_authors(conn: &mut PgConnection, books: Vec<Book>) -> Result<Vec<BookOutput>> {
+    let authors = load_authors(conn, &books)?;
+    Ok(books
+        .into_iter()
+        .zip(authors)
+        .map(|(book, authors)| BookOutput::new(book, authors))
+        .collect())
+}
+
+/// Like [`with_authors`], for books paired with some value such as a score
+pub(crate) fn with_authors_paired<T>(
+    conn: &mut PgConnection,
+    rows: Vec<(Book, T)>,
+) -> Result<Vec<(BookOutput, T)>> {
+    let (books, values): (Vec<Book>, Vec<T>) = rows.into_iter().unzip();
+    Ok(with_authors(conn, books)?.into_iter().zip(values).collect())
+}
+
+/// A book returned by vector search, with the cosine similarity between its
+/// embedding and the query (1 is identical, 0 unrelated)
+#[derive(Serialize, Debug)]
+pub struct ScoredBookOutput {
+    #[serde(flatten)]
+    pub book: BookOutput,
+    pub score: f32,
+}
+
+/// Returned when the query text embeds to a zero vector (e.g. it is empty or
+/// shorter than an n-gram), which has no direction to compare against
+pub(crate) fn query_too_short() -> Error {
+    Error::BadRequest("Query too short to search by similarity".to_string())
+}
+
+/// `ILIKE` pattern matching `text` anywhere, with the wildcards `%` and `_`
+/// in `text` escaped so they match themselves
+pub(crate) fn contains_pattern(text: &str) -> String {
+    let mut pattern = String::with_capacity(text.len() + 2);
+    pattern.push('%');
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Orders and pages an authors query by `page`; `relevance` scores each
+/// author for lists that can be sorted by relevance
+pub(crate) fn page_authors<'a>(
+    query: authors::BoxedQuery<'a, Pg>,
+    page: &PageRequest,
+    relevance: Option<&dyn Fn() -> Boxed<'a, authors::table, Float4>>,
+) -> Result<authors::BoxedQuery<'a, Pg>> {
+    let position = page.position()?;
+    let id = || -> Boxed<'a, authors::table, Integer> { Box::new(authors::id) };
+    let query = match page.sort {
+        SortBy::Title => keyset(
+            query,
+            page.order,
+            || -> Boxed<'a, authors::table, Text> { Box::new(authors::name) },
+            id,
+            after(position, SortKey::into_text)?,
+        ),
+        SortBy::Year => keyset(
+            query,
+            page.order,
+            || -> Boxed<'a, authors::table, Integer> { Box::new(authors::birth_year) },
+            id,
+            after(position, SortKey::into_int)?,
+        ),
+        SortBy::Id => keyset(
+            query,
+            page.order,
+            id,
+            id,
+            after(position, SortKey::into_int)?,
+        ),
+        SortBy::Relevance => {
+            let score = relevance.ok_or_else(no_relevance)?;
+            keyset(
+                query,
+                page.order,
+                score,
+                id,
+                after(position, SortKey::into_float)?,
+            )
+        }
+    };
+    Ok(query.limit(page.fetch_limit()))
+}
+
+/// Sort key of `author` in a list ordered by a column
+pub(crate) fn author_position(author: &Author, sort: SortBy) -> (SortKey, i32) {
+    let key = match sort {
+        SortBy::Title => SortKey::Text(author.name.clone()),
+        SortBy::Year => SortKey::Int(author.birth_year),
+        SortBy::Id | SortBy::Relevance => SortKey::Int(author.id),
+    };
+    (key, author.id)
+}
+
+/// Orders and pages a books query by `page`; `relevance` scores each book
+/// for lists that can be sorted by relevance
+pub(crate) fn page_books<'a>(
+    query: books::BoxedQuery<'a, Pg>,
+    page: &PageRequest,
+    relevance: Option<&dyn Fn() -> Boxed<'a, books::table, Float4>>,
+) -> Result<books::BoxedQuery<'a, Pg>> {
+    let position = page.position()?;
+    let id = || -> Boxed<'a, books::table, Integer> { Box::new(books::id) };
+    let query = match page.sort {
+        SortBy::Title => keyset(
+            query,
+            page.order,
+            || -> Boxed<'a, books::table, Text> { Box::new(books::title) },
+            id,
+            after(position, SortKey::into_text)?,
+        ),
+        SortBy::Year => keyset(
+            query,
+            page.order,
+            || -> Boxed<'a, books::table, Integer> { Box::new(books::publication_year) },
+            id,
+            after(position, SortKey::into_int)?,
+        ),
+        SortBy::Id => keyset(
+            query,
+            page.order,
+            id,
+            id,
+            after(position, SortKey::into_int)?,
+        ),
+        SortBy::Relevance => {
+            let score = relevance.ok_or_else(no_relevance)?;
+            keyset(
+                query,
+                page.order,
+                score,
+                id,
+                after(position, SortKey::into_float)?,
+            )
+        }
+    };
+    Ok(query.limit(page.fetch_limit()))
+}
+
+/// Sort key of `book` in a list ordered by a column
+pub(crate) fn book_position(book: &Book, sort: SortBy) -> (SortKey, i32) {
+    let key = match sort {
+        SortBy::Title => SortKey::Text(book.title.clone()),
+        SortBy::Year => SortKey::Int(book.publication_year),
+        SortBy::Id | SortBy::Relevance => SortKey::Int(book.id),
+    };
+    (key, book.id)
+}
+
+/// Retrieves authors by partial name match, one page at a time
+pub fn get_authors_by_name(
+    conn: &mut PgConnection,
+    name: &str,
+    page: &PageRequest,
+) -> Result<Paginated<AuthorOutput>> {
+    let pattern = contains_pattern(name);
+    let matching = || live_authors().filter(authors::name.ilike(pattern.clone()));
+
+    let authors = page_authors(matching(), page, None)?
         .select(Author::as_select())
         .load(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to query authors by name: {}", e))?;
+        .context("Failed to query authors by name")?;
+    let total = page
+        .with_total
+        .then(|| matching().count().get_result::<i64>(conn))
+        .transpose()
+        .context("Failed to count authors by name")?;
 
-    Ok(authors.into_iter().map(AuthorOutput::from).collect())
+    Ok(Paginated::from_rows(authors, page, total, |author| {
+        author_position(author, page.sort)
+    })
+    .map(AuthorOutput::from))
 }
 
-/// Retrieves books by partial title match
-pub fn get_books_by_title(conn: &mut PgConnection, title: &str) -> Result<Vec<BookOutput>> {
-    let pattern = format!("%{}%", title);
+/// Retrieves books by partial title match, one page at a time
+pub fn get_books_by_title(
+    conn: &mut PgConnection,
+    title: &str,
+    page: &PageRequest,
+) -> Result<Paginated<BookOutput>> {
+    let pattern = contains_pattern(title);
+    let matching = || live_books().filter(books::title.ilike(pattern.clone()));
 
-    let books = books::table
-        .filter(books::title.ilike(&pattern))
+    let books = page_books(matching(), page, None)?
         .select(Book::as_select())
         .load(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to query books by title: {}", e))?;
+        .context("Failed to query books by title")?;
+    let total = page
+        .with_total
+        .then(|| matching().count().get_result::<i64>(conn))
+        .transpose()
+        .context("Failed to count books by title")?;
 
-    Ok(books.into_iter().map(BookOutput::from).collect())
+    Paginated::from_rows(books, page, total, |book| book_position(book, page.sort))
+        .try_map_items(|books| with_authors(conn, books))
 }
 
 /// Retrieves a specific author by ID
 pub fn get_author_by_id(conn: &mut PgConnection, author_id: i32) -> Result<AuthorOutput> {
     let author = authors::table
         .find(author_id)
+        .filter(authors::deleted_at.is_null())
         .select(Author::as_select())
         .first(conn)
-        .map_err(|e| anyhow::anyhow!("Author with ID {} not found: {}", author_id, e))?;
+        .optional()
+        .with_context(|| format!("Failed to query author {}", author_id))?;
 
-    Ok(AuthorOutput::from(author))
+    author
+        .map(AuthorOutput::from)
+        .ok_or_else(|| Error::not_found("Author", author_id))
 }
 
 /// Retrieves a specific book by ID
 pub fn get_book_by_id(conn: &mut PgConnection, book_id: i32) -> Result<BookOutput> {
     let book = books::table
         .find(book_id)
+        .filter(books::deleted_at.is_null())
         .select(Book::as_select())
         .first(conn)
-        .map_err(|e| anyhow::anyhow!("Book with ID {} not found: {}", book_id, e))?;
+        .optional()
+        .with_context(|| format!("Failed to query book {}", book_id))?;
 
-    Ok(BookOutput::from(book))
+    let book = book.ok_or_else(|| Error::not_found("Book", book_id))?;
+    let mut books = with_authors(conn, vec![book])?;
+    Ok(books.remove(0))
 }
 
-/// Retrieves all books written by a specific author (by author ID)
-pub fn get_books_by_author_id(conn: &mut PgConnection, author_id: i32) -> Result<Vec<BookOutput>> {
-    let books = books::table
-        .inner_join(books_authors::table)
-        .filter(books_authors::author_id.eq(author_id))
+fn author_exists(conn: &mut PgConnection, author_id: i32) -> Result<bool> {
+    diesel::select(diesel::dsl::exists(
+        authors::table
+            .find(author_id)
+            .filter(authors::deleted_at.is_null()),
+    ))
+    .get_result(conn)
+    .with_context(|| format!("Failed to query author {}", author_id))
+}
+
+fn book_exists(conn: &mut PgConnection, book_id: i32) -> Result<bool> {
+    diesel::select(diesel::dsl::exists(
+        books::table
+            .find(book_id)
+            .filter(books::deleted_at.is_null()),
+    ))
+    .get_result(conn)
+    .with_context(|| format!("Failed to query book {}", book_id))
+}
+
+/// Retrieves the books written by a specific author (by author ID), one
+/// page at a time
+pub fn get_books_by_author_id(
+    conn: &mut PgConnection,
+    author_id: i32,
+    page: &PageRequest,
+) -> Result<Paginated<BookOutput>> {
+    if !author_exists(conn, author_id)? {
+        return Err(Error::not_found("Author", author_id));
+    }
+
+    let written = || {
+        live_books().filter(
+            books::id.eq_any(
+                books_authors::table
+                    .filter(books_authors::author_id.eq(author_id))
+                    .select(books_authors::book_id),
+            ),
+        )
+    };
+    let books = page_books(written(), page, None)?
         .select(Book::as_select())
         .load(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to query books by author ID: {}", e))?;
+        .context("Failed to query books by author ID")?;
+    let total = page
+        .with_total
+        .then(|| written().count().get_result::<i64>(conn))
+        .transpose()
+        .context("Failed to count books by author ID")?;
 
-    Ok(books.into_iter().map(BookOutput::from).collect())
+    Paginated::from_rows(books, page, total, |book| book_position(book, page.sort))
+        .try_map_items(|books| with_authors(conn, books))
 }
 
-/// Retrieves all books written by a specific author (by author name, partial match)
+/// Retrieves the books written by authors whose name contains
+/// `author_name`, one page at a time
 pub fn get_books_by_author_name(
     conn: &mut PgConnection,
     author_name: &str,
-) -> Result<Vec<BookOutput>> {
-    let pattern = format!("%{}%", author_name);
+    page: &PageRequest,
+) -> Result<Paginated<BookOutput>> {
+    let pattern = contains_pattern(author_name);
+    // A subquery rather than a join, so books by several matching authors
+    // appear once
+    let matching = || {
+        live_books().filter(
+            books::id.eq_any(
+                books_authors::table
+                    .inner_join(authors::table)
+                    .filter(authors::deleted_at.is_null())
+                    .filter(authors::name.ilike(pattern.clone()))
+                    .select(books_authors::book_id),
+            ),
+        )
+    };
 
-    let books = books::table
-        .inner_join(books_authors::table)
-        .inner_join(authors::table.on(authors::id.eq(books_authors::author_id)))
-        .filter(authors::name.ilike(&pattern))
+    let books = page_books(matching(), page, None)?
         .select(Book::as_select())
         .load(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to query books by author name: {}", e))?;
+        .context("Failed to query books by author name")?;
+    let total = page
+        .with_total
+        .then(|| matching().count().get_result::<i64>(conn))
+        .transpose()
+        .context("Failed to count books by author name")?;
 
-    Ok(books.into_iter().map(BookOutput::from).collect())
+    Paginated::from_rows(books, page, total, |book| book_position(book, page.sort))
+        .try_map_items(|books| with_authors(conn, books))
 }
 
-/// Retrieves all authors for a specific book (by book ID)
+/// Retrieves all authors for a specific book (by book ID), ordered by
+/// name
 pub fn get_authors_by_book_id(conn: &mut PgConnection, book_id: i32) -> Result<Vec<AuthorOutput>> {
+    if !book_exists(conn, book_id)? {
+        return Err(Error::not_found("Book", book_id));
+    }
+
     let authors = authors::table
         .inner_join(books_authors::table)
         .filter(books_authors::book_id.eq(book_id))
+        .filter(authors::deleted_at.is_null())
+        .order((authors::name, authors::id))
         .select(Author::as_select())
         .load(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to query authors by book ID: {}", e))?;
+        .context("Failed to query authors by book ID")?;
 
     Ok(authors.into_iter().map(AuthorOutput::from).collect())
 }
 
-/// Performs a similarity search for books using pgvector cosine distance
-/// Returns books ordered by cosine distance (lower distance = more similar)
-pub fn similarity_search_by_prompt(
+/// Lists the books matching `filters` with their authors, one page at a
+/// time
+pub fn list_books(
+    conn: &mut PgConnection,
+    filters: &SearchFilters,
+    page: &PageRequest,
+) -> Result<Paginated<BookOutput>> {
+    let matching = || apply_filters(live_books(), filters);
+
+    let books = page_books(matching(), page, None)?
+        .select(Book::as_select())
+        .load(conn)
+        .context("Failed to list books")?;
+    let total = page
+        .with_total
+        .then(|| matching().count().get_result::<i64>(conn))
+        .transpose()
+        .context("Failed to count books")?;
+
+    Paginated::from_rows(books, page, total, |book| book_position(book, page.sort))
+        .try_map_items(|books| with_authors(conn, books))
+}
+
+/// Lists the books published from `year_from` to `year_to`, inclusive
+pub fn get_books_by_year(
     conn: &mut PgConnection,
+    year_from: i32,
+    year_to: i32,
+    page: &PageRequest,
+) -> Result<Paginated<BookOutput>> {
+    let filters = SearchFilters {
+        year_from: Some(year_from),
+        year_to: Some(year_to),
+        ..SearchFilters::default()
+    };
+    list_books(conn, &filters, page)
+}
+
+/// Lists the books written by at least one author from `country`
+/// (case-insensitive)
+pub fn get_books_by_author_country(
+    conn: &mut PgConnection,
+    country: &str,
+    page: &PageRequest,
+) -> Result<Paginated<BookOutput>> {
+    let filters = SearchFilters {
+        author_country: Some(country.to_string()),
+        ..SearchFilters::default()
+    };
+    list_books(conn, &filters, page)
+}
+
+/// `books.embedding` cast to `dimension`, written exactly like the
+/// expression of the per-dimension HNSW indexes so Postgres can use them
+pub(crate) fn embedding_with_dimension(dimension: usize) -> SqlLiteral<Nullable<VectorType>> {
+    sql(&format!("(books.embedding::vector({}))", dimension))
+}
+
+/// Restricts rows to vectors of `dimension` elements; matches the predicate
+/// of the partial HNSW index for that dimension
+pub(crate) fn embedding_has_dimension(dimension: usize) -> SqlLiteral<Bool> {
+    sql(&format!("vector_dims(books.embedding) = {}", dimension))
+}
+
+/// Leaves out zero vectors, whose cosine distance to anything is NaN. The
+/// embedders no longer store them, but older rows may hold some.
+pub(crate) fn embedding_is_nonzero() -> SqlLiteral<Bool> {
+    sql("vector_norm(books.embedding) > 0")
+}
+
+/// pgvector's default `hnsw.ef_search`
+const DEFAULT_EF_SEARCH: i32 = 40;
+
+/// Sets a configuration parameter until the end of the current transaction
+pub(crate) fn set_local(conn: &mut PgConnection, name: &str, value: &str) -> QueryResult<()> {
+    diesel::sql_query("SELECT set_config($1, $2, true)")
+        .bind::<Text, _>(name)
+        .bind::<Text, _>(value)
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Tunes the HNSW index scan of the current transaction for a query
+/// returning `limit` rows. The index yields at most `hnsw.ef_search`
+/// candidates, which filters then thin out; iterative scans keep fetching
+/// until enough rows pass, at the cost of results only roughly ordered.
+pub(crate) fn prepare_hnsw_scan(conn: &mut PgConnection, limit: i32) -> QueryResult<()> {
+    set_local(
+        conn,
+        "hnsw.ef_search",
+        &limit.max(DEFAULT_EF_SEARCH).to_string(),
+    )?;
+    set_local(conn, "hnsw.iterative_scan", "relaxed_order")
+}
+
+/// Runs `candidates` as a nearest-neighbour search around `embedding`,
+/// keeping only vectors produced by `embedder` and scoring each book with
+/// its cosine similarity
+fn nearest_books<'a>(
+    conn: &mut PgConnection,
+    candidates: books::BoxedQuery<'a, Pg>,
+    embedder: &'a dyn Embedder,
+    embedding: &'a Vector,
+    limit: i32,
+    min_score: Option<f32>,
+) -> Result<Vec<(Book, f32)>> {
+    // Use pgvector expression methods for similarity search, only comparing
+    // against vectors produced by the same embedder version
+    let distance = || embedding_with_dimension(embedder.dimension()).cosine_distance(embedding);
+    let query = candidates
+        .filter(books::embedding_version.eq(embedder.version()))
+        .filter(embedding_has_dimension(embedder.dimension()))
+        .filter(embedding_is_nonzero())
+        .order(distance())
+        .limit(limit as i64)
+        .select((Book::as_select(), distance()));
+    let similar_books: Vec<(Book, Option<f64>)> = conn
+        .transaction(|conn| {
+            prepare_hnsw_scan(conn, limit)?;
+            query.load(conn)
+        })
+        .context("Failed to execute vector similarity query")?;
+
+    let mut results: Vec<(Book, f32)> = similar_books
+        .into_iter()
+        .filter_map(|(book, distance)| Some((book, 1.0 - distance? as f32)))
+        .collect();
+
+    // Iterative scans return rows only roughly ordered; once sorted, the
+    // threshold keeps exactly the best matches above `min_score`. A NaN
+    // score would sort first and pass no threshold check, so it goes too.
+    results.retain(|(_, score)| score.is_finite() && min_score.is_none_or(|min| *score >= min));
+    results.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    Ok(results)
+}
+
+fn scored_with_authors(
+    conn: &mut PgConnection,
+    rows: Vec<(Book, f32)>,
+) -> Result<Vec<ScoredBookOutput>> {
+    Ok(with_authors_paired(conn, rows)?
+        .into_iter()
+        .map(|(book, score)| ScoredBookOutput { book, score })
+        .collect())
+}
+
+/// Books matching `filters` nearest to `query_text`, with their similarity;
+/// see [`similarity_search_by_prompt`]
+pub(crate) fn nearest_to_prompt(
+    conn: &mut PgConnection,
+    embedder: &dyn Embedder,
     query_text: &str,
     limit: i32,
-) -> Result<Vec<BookOutput>> {
+    min_score: Option<f32>,
+    filters: &SearchFilters,
+) -> Result<Vec<(Book, f32)>> {
     // Compute the embedding for the query text
-    let query_embedding = compute_embedding(query_text)?;
+    let query_embedding = embedder.embed(query_text)?;
+    if norm(&query_embedding) == 0.0 {
+        return Err(query_too_short());
+    }
     let embedding_vec = Vector::from(query_embedding);
 
-    // Use pgvector expression methods for similarity search
-    let similar_books = books::table
-        .order(books::embedding.cosine_distance(&embedding_vec))
-        .limit(limit as i64)
-        .select(Book::as_select())
-        .load(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to execute vector similarity query: {}", e))?;
+    nearest_books(
+        conn,
+        apply_filters(live_books(), filters),
+        embedder,
+        &embedding_vec,
+        limit,
+        min_score,
+    )
+}
 
-    Ok(similar_books.into_iter().map(BookOutput::from).collect())
+/// Performs a similarity search for books using pgvector cosine distance.
+/// Returns up to `limit` books matching `filters`, ordered from most to
+/// least similar, leaving out those whose similarity is below `min_score`.
+pub fn similarity_search_by_prompt(
+    conn: &mut PgConnection,
+    embedder: &dyn Embedder,
+    query_text: &str,
+    limit: i32,
+    min_score: Option<f32>,
+    filters: &SearchFilters,
+) -> Result<Vec<ScoredBookOutput>> {
+    let rows = nearest_to_prompt(conn, embedder, query_text, limit, min_score, filters)?;
+    scored_with_authors(conn, rows)
 }
 
-/// Finds books with similar embeddings to a given book ID using cosine distance
+/// Finds the books closest to a given book, using its stored embedding.
+/// The book itself is never returned, and with `exclude_same_authors` neither
+/// are other books sharing one of its authors.
 pub fn find_similar_books(
     conn: &mut PgConnection,
+    embedder: &dyn Embedder,
     book_id: i32,
     limit: i32,
-) -> Result<Vec<BookOutput>> {
-    // Get the book information
-    let target_book = get_book_by_id(conn, book_id)?;
+    exclude_same_authors: bool,
+) -> Result<Vec<ScoredBookOutput>> {
+    let source: Option<(String, Option<Vector>, Option<String>)> = books::table
+        .find(book_id)
+        .filter(books::deleted_at.is_null())
+        .select((
+            books::abstract_text,
+            books::embedding,
+            books::embedding_version,
+        ))
+        .first(conn)
+        .optional()
+        .with_context(|| format!("Failed to query book {}", book_id))?;
+    let (abstract_text, embedding, embedding_version) =
+        source.ok_or_else(|| Error::not_found("Book", book_id))?;
 
-    // Compute embedding for the book's abstract text
-    let query_text = &target_book.abstract_text;
+    // Reuse the stored vector; only a book not yet re-embedded with the
+    // current embedder needs its abstract embedded again
+    let embedding = match embedding {
+        Some(embedding) if embedding_version.as_deref() == Some(embedder.version()) => embedding,
+        _ => Vector::from(embedder.embed(&abstract_text)?),
+    };
+    if norm(&embedding) == 0.0 {
+        return Ok(Vec::new());
+    }
+
+    let mut candidates = live_books().filter(books::id.ne(book_id));
+    if exclude_same_authors {
+        let source_authors = books_authors::table
+            .filter(books_authors::book_id.eq(book_id))
+            .select(books_authors::author_id)
+            .load::<i32>(conn)
+            .with_context(|| format!("Failed to query authors of book {}", book_id))?;
+        let same_author_books = books_authors::table
+            .filter(books_authors::author_id.eq_any(source_authors))
+            .select(books_authors::book_id);
+        candidates = candidates.filter(diesel::dsl::not(books::id.eq_any(same_author_books)));
+    }
 
-    // Use the existing similarity search function to find similar books
-    similarity_search_by_prompt(conn, query_text, limit)
+    let rows = nearest_books(conn, candidates, embedder, &embedding, limit, None)?;
+    scored_with_authors(conn, rows)
 }
 
 #[cfg(test)]
@@ -178,6 +697,8 @@ mod tests {
     use super::*;
     use crate::database::initialization::establish_connection;
     use crate::database::insertion::{insert_author, insert_book, link_book_author};
+    use crate::database::pagination::SortOrder;
+    use crate::embedding::HashingEmbedder;
     use crate::models::{NewAuthor, NewBook};
 
     // Helper function to check if database is available
@@ -185,6 +706,12 @@ mod tests {
         std::env::var("DATABASE_URL").is_ok()
     }
 
+    #[test]
+    fn test_contains_pattern_escapes_wildcards() {
+        assert_eq!(contains_pattern("Assis"), "%Assis%");
+        assert_eq!(contains_pattern("100%_a\\b"), "%100\\%\\_a\\\\b%");
+    }
+
     #[test]
     fn test_get_authors_by_name() {
         if !db_available() {
@@ -198,9 +725,10 @@ mod tests {
         let author = insert_author(&mut conn, &new_author).expect("Failed to insert author");
 
         // Test query
-        let results = get_authors_by_name(&mut conn, "Query").expect("Failed to query authors");
-        assert!(!results.is_empty());
-        assert!(results.iter().any(|a| a.name.contains("Query")));
+        let results = get_authors_by_name(&mut conn, "Query", &PageRequest::first(100, SortBy::Id))
+            .expect("Failed to query authors");
+        assert!(!results.items.is_empty());
+        assert!(results.items.iter().any(|a| a.name.contains("Query")));
 
         // Cleanup
         diesel::delete(authors::table.filter(authors::id.eq(author.id)))
@@ -217,14 +745,20 @@ mod tests {
         let mut conn = establish_connection().expect("Failed to connect to database");
 
         // Insert test data
-        let new_book = NewBook::new("Test Query Book", 2025, "A test book for querying")
-            .expect("Failed to create book");
+        let new_book = NewBook::new(
+            "Test Query Book",
+            2025,
+            "A test book for querying",
+            &HashingEmbedder::new(),
+        )
+        .expect("Failed to create book");
         let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
 
         // Test query
-        let results = get_books_by_title(&mut conn, "Query").expect("Failed to query books");
-        assert!(!results.is_empty());
-        assert!(results.iter().any(|b| b.title.contains("Query")));
+        let results = get_books_by_title(&mut conn, "Query", &PageRequest::first(100, SortBy::Id))
+            .expect("Failed to query books");
+        assert!(!results.items.is_empty());
+        assert!(results.items.iter().any(|b| b.title.contains("Query")));
 
         // Cleanup
         diesel::delete(books::table.filter(books::id.eq(book.id)))
@@ -232,6 +766,165 @@ mod tests {
             .ok();
     }
 
+    #[test]
+    fn test_get_by_id_and_relationships() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+
+        let author = insert_author(
+            &mut conn,
+            &NewAuthor::new("By Id Test Author", 1900, "Chile"),
+        )
+        .expect("Failed to insert author");
+        let mut books_ids = Vec::new();
+        for (title, year) in [("By Id Book Two", 1960), ("By Id Book One", 1950)] {
+            let new_book = NewBook::new(title, year, "A book fetched by its id", &embedder)
+                .expect("Failed to create book");
+            let book = insert_book(&mut conn, &new_book).unwrap();
+            link_book_author(&mut conn, book.id, author.id).unwrap();
+            books_ids.push(book.id);
+        }
+
+        let found = get_author_by_id(&mut conn, author.id).unwrap();
+        assert_eq!(found.name, "By Id Test Author");
+        let found = get_book_by_id(&mut conn, books_ids[0]).unwrap();
+        assert_eq!(found.title, "By Id Book Two");
+        assert_eq!(found.authors.len(), 1);
+        assert_eq!(found.authors[0].name, "By Id Test Author");
+
+        let books =
+            get_books_by_author_id(&mut conn, author.id, &PageRequest::first(10, SortBy::Year))
+                .unwrap();
+        let titles: Vec<&str> = books.items.iter().map(|b| b.title.as_str()).collect();
+        assert_eq!(titles, vec!["By Id Book One", "By Id Book Two"]);
+
+        let authors = get_authors_by_book_id(&mut conn, books_ids[0]).unwrap();
+        assert_eq!(authors.len(), 1);
+        assert_eq!(authors[0].id, author.id);
+
+        let is_not_found = |error: Error| matches!(error, Error::NotFound(_));
+        assert!(is_not_found(get_author_by_id(&mut conn, -1).unwrap_err()));
+        assert!(is_not_found(get_book_by_id(&mut conn, -1).unwrap_err()));
+        assert!(is_not_found(
+            get_books_by_author_id(&mut conn, -1, &PageRequest::first(10, SortBy::Year))
+                .unwrap_err()
+        ));
+        assert!(is_not_found(
+            get_authors_by_book_id(&mut conn, -1).unwrap_err()
+        ));
+
+        // Cleanup
+        diesel::delete(books_authors::table.filter(books_authors::author_id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(books::table.filter(books::id.eq_any(&books_ids)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(authors::table.filter(authors::id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_get_books_by_year_and_country() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+
+        let author = insert_author(
+            &mut conn,
+            &NewAuthor::new("Listing Test Author", 1800, "Listing Test Country"),
+        )
+        .expect("Failed to insert author");
+        let mut books_ids = Vec::new();
+        for (title, year) in [
+            ("Listing Book B", 1851),
+            ("Listing Book A", 1851),
+            ("Listing Book C", 1852),
+        ] {
+            let new_book = NewBook::new(title, year, "A book listed by year", &embedder)
+                .expect("Failed to create book");
+            let book = insert_book(&mut conn, &new_book).unwrap();
+            link_book_author(&mut conn, book.id, author.id).unwrap();
+            books_ids.push(book.id);
+        }
+        let results = get_books_by_year(
+            &mut conn,
+            1851,
+            1851,
+            &PageRequest::first(100, SortBy::Title),
+        )
+        .expect("Failed to query books by year");
+        let titles: Vec<&str> = results
+            .items
+            .iter()
+            .filter(|r| books_ids.contains(&r.id))
+            .map(|r| r.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Listing Book A", "Listing Book B"]);
+        assert!(results.items.iter().all(|r| r.publication_year == 1851));
+
+        let by_year_desc = PageRequest {
+            order: SortOrder::Desc,
+            with_total: true,
+            ..PageRequest::first(100, SortBy::Year)
+        };
+        let results = get_books_by_author_country(&mut conn, "listing test country", &by_year_desc)
+            .expect("Failed to query books by country");
+        assert_eq!(results.items.len(), 3);
+        assert_eq!(results.total, Some(3));
+        assert!(results.next_cursor.is_none());
+        assert_eq!(results.items[0].title, "Listing Book C");
+        assert!(results.items.iter().all(|r| r.authors.len() == 1));
+        assert_eq!(results.items[0].authors[0].id, author.id);
+
+        // Walking the list two books at a time visits every book once
+        let mut page = PageRequest {
+            limit: 2,
+            ..by_year_desc
+        };
+        let mut visited = Vec::new();
+        loop {
+            let results = get_books_by_author_country(&mut conn, "Listing Test Country", &page)
+                .expect("Failed to query books by country");
+            visited.extend(results.items.iter().map(|r| r.id));
+            match results.next_cursor {
+                Some(cursor) => page.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        let expected: Vec<i32> = results.items.iter().map(|r| r.id).collect();
+        assert_eq!(visited, expected);
+
+        // Books have no relevance to sort by
+        let error = get_books_by_year(
+            &mut conn,
+            1851,
+            1851,
+            &PageRequest::first(10, SortBy::Relevance),
+        )
+        .unwrap_err();
+        assert!(matches!(error, Error::Validation(_)));
+
+        // Cleanup
+        diesel::delete(books_authors::table.filter(books_authors::author_id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(books::table.filter(books::id.eq_any(&books_ids)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(authors::table.filter(authors::id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
     #[test]
     fn test_similarity_search_by_prompt() {
         if !db_available() {
@@ -245,6 +938,7 @@ mod tests {
             "Similarity Embedding Test Book",
             2025,
             "This is a book about testing embeddings",
+            &HashingEmbedder::new(),
         )
         .expect("Failed to create book");
         let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
@@ -256,17 +950,41 @@ mod tests {
         println!("Description: {}", book.abstract_text);
 
         // Test embedding query
-        let results = similarity_search_by_prompt(&mut conn, "similarity testing embeddings", 5)
-            .expect("Failed to query books by embedding");
+        let results = similarity_search_by_prompt(
+            &mut conn,
+            &HashingEmbedder::new(),
+            "similarity testing embeddings",
+            5,
+            None,
+            &SearchFilters::default(),
+        )
+        .expect("Failed to query books by embedding");
 
         // Should find at least our test book
         assert!(!results.is_empty());
+        assert!(
+            results
+                .windows(2)
+                .all(|pair| pair[0].score >= pair[1].score)
+        );
 
         // Print the results
         for result in results {
-            println!("Title: {}", result.title);
+            println!("Title: {} ({:.3})", result.book.title, result.score);
         }
 
+        // A threshold above every score leaves nothing
+        let results = similarity_search_by_prompt(
+            &mut conn,
+            &HashingEmbedder::new(),
+            "similarity testing embeddings",
+            5,
+            Some(1.01),
+            &SearchFilters::default(),
+        )
+        .expect("Failed to query books by embedding");
+        assert!(results.is_empty());
+
         // Cleanup
         diesel::delete(books::table.filter(books::id.eq(book.id)))
             .execute(&mut conn)
@@ -274,4 +992,204 @@ mod tests {
 
         println!("Similarity search by prompt test completed successfully!");
     }
+
+    #[test]
+    fn test_similarity_search_skips_zero_vectors() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+        let mut books_ids = Vec::new();
+        for (title, embedding) in [
+            ("Zero Vector Search Book", vec![0.0; embedder.dimension()]),
+            (
+                "Nonzero Vector Search Book",
+                embedder.embed("a zero vector search").unwrap(),
+            ),
+        ] {
+            let mut new_book = NewBook::new(title, 2025, "A zero vector search", &embedder)
+                .expect("Failed to create book");
+            new_book.embedding = Some(Vector::from(embedding));
+            books_ids.push(insert_book(&mut conn, &new_book).unwrap().id);
+        }
+
+        let results = similarity_search_by_prompt(
+            &mut conn,
+            &embedder,
+            "zero vector search",
+            100,
+            None,
+            &SearchFilters::default(),
+        )
+        .expect("Failed to query books by embedding");
+        assert!(results.iter().all(|r| r.score.is_finite()));
+        assert!(results.iter().all(|r| r.book.id != books_ids[0]));
+        assert!(results.iter().any(|r| r.book.id == books_ids[1]));
+
+        // Cleanup
+        diesel::delete(books::table.filter(books::id.eq_any(&books_ids)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_similarity_search_rejects_short_query() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let result = similarity_search_by_prompt(
+            &mut conn,
+            &HashingEmbedder::new(),
+            "ab",
+            5,
+            None,
+            &SearchFilters::default(),
+        );
+        assert!(matches!(result.unwrap_err(), Error::BadRequest(_)));
+    }
+
+    #[test]
+    fn test_similarity_search_with_filters() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+
+        let author = insert_author(
+            &mut conn,
+            &NewAuthor::new("Filtered Search Test Author", 1900, "Portugal"),
+        )
+        .expect("Failed to insert author");
+        let mut books_ids = Vec::new();
+        for year in [1890, 1950, 2010] {
+            let new_book = NewBook::new(
+                &format!("Filtered Search Book {}", year),
+                year,
+                "A lighthouse keeper watches the sea",
+                &embedder,
+            )
+            .expect("Failed to create book");
+            let book = insert_book(&mut conn, &new_book).unwrap();
+            link_book_author(&mut conn, book.id, author.id).unwrap();
+            books_ids.push(book.id);
+        }
+
+        let search = |conn: &mut PgConnection, filters: &SearchFilters| {
+            similarity_search_by_prompt(conn, &embedder, "lighthouse keeper sea", 10, None, filters)
+                .expect("Failed to query books by embedding")
+                .into_iter()
+                .map(|r| r.book.id)
+                .collect::<Vec<_>>()
+        };
+
+        // Every filtered-in book comes back, even with others more similar
+        let ids = search(
+            &mut conn,
+            &SearchFilters {
+                author_ids: Some(vec![author.id]),
+                ..SearchFilters::default()
+            },
+        );
+        assert_eq!(ids.len(), 3);
+
+        let ids = search(
+            &mut conn,
+            &SearchFilters {
+                author_ids: Some(vec![author.id]),
+                year_from: Some(1900),
+                year_to: Some(2000),
+                ..SearchFilters::default()
+            },
+        );
+        assert_eq!(ids, vec![books_ids[1]]);
+
+        let ids = search(
+            &mut conn,
+            &SearchFilters {
+                author_ids: Some(vec![author.id]),
+                author_country: Some("brazil".to_string()),
+                ..SearchFilters::default()
+            },
+        );
+        assert!(ids.is_empty());
+
+        // Cleanup
+        diesel::delete(books_authors::table.filter(books_authors::author_id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(books::table.filter(books::id.eq_any(&books_ids)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(authors::table.filter(authors::id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_find_similar_books() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+
+        let author = insert_author(
+            &mut conn,
+            &NewAuthor::new("Similar Books Test Author", 1900, "Brazil"),
+        )
+        .expect("Failed to insert author");
+        let mut books_ids = Vec::new();
+        for title in [
+            "Similar Source Book",
+            "Similar Sibling Book",
+            "Similar Other Book",
+        ] {
+            let new_book = NewBook::new(
+                title,
+                2025,
+                "A lighthouse keeper watches the sea",
+                &embedder,
+            )
+            .expect("Failed to create book");
+            books_ids.push(insert_book(&mut conn, &new_book).unwrap().id);
+        }
+        let (source, sibling, other) = (books_ids[0], books_ids[1], books_ids[2]);
+        link_book_author(&mut conn, source, author.id).unwrap();
+        link_book_author(&mut conn, sibling, author.id).unwrap();
+
+        let results = find_similar_books(&mut conn, &embedder, source, 100, false)
+            .expect("Failed to find similar books");
+        assert!(results.iter().all(|r| r.book.id != source));
+        let sibling_result = results.iter().find(|r| r.book.id == sibling).unwrap();
+        assert_eq!(sibling_result.book.authors[0].id, author.id);
+        assert!(results.iter().any(|r| r.book.id == other));
+
+        let results = find_similar_books(&mut conn, &embedder, source, 100, true)
+            .expect("Failed to find similar books");
+        assert!(results.iter().all(|r| r.book.id != sibling));
+        assert!(results.iter().any(|r| r.book.id == other));
+
+        assert!(matches!(
+            find_similar_books(&mut conn, &embedder, -1, 10, false),
+            Err(Error::NotFound(_))
+        ));
+
+        // Cleanup
+        diesel::delete(books_authors::table.filter(books_authors::author_id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(books::table.filter(books::id.eq_any(&books_ids)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(authors::table.filter(authors::id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+    }
 }
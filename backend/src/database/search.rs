@@ -0,0 +1,799 @@
+use std::collections::HashMap;
+
+use diesel::dsl::sql;
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Double, Float4, Nullable, Text};
+use pgvector::{Vector, VectorExpressionMethods};
+use serde::{Deserialize, Serialize};
+
+use crate::database::pagination::{Boxed, PageRequest, Paginated, SortBy, SortKey};
+use crate::database::query::{
+    AuthorOutput, BookOutput, author_position, book_position, contains_pattern,
+    embedding_has_dimension, embedding_is_nonzero, embedding_with_dimension, live_authors,
+    live_books, nearest_to_prompt, page_authors, page_books, prepare_hnsw_scan, query_too_short,
+    set_local, with_authors_paired,
+};
+use crate::embedding::Embedder;
+use crate::embedding::vector::norm;
+use crate::error::{Error, Result, ResultExt};
+use crate::models::{Author, Book};
+use crate::params::{from_text_or_value, list_from_text_or_value};
+use crate::schema::{authors, books, books_authors};
+
+/// Damping constant of reciprocal rank fusion; 60 is the value from the
+/// original paper (Cormack et al., 2009) and rarely needs tuning
+pub const RRF_K: f32 = 60.0;
+
+/// How many candidates each retriever contributes per requested result
+const CANDIDATES_PER_RESULT: i32 = 4;
+
+/// Upper bound on the candidates fetched from each retriever
+const MAX_CANDIDATES: i32 = 200;
+
+/// Word similarity a fuzzy match must reach when the caller sets none; high
+/// enough to skip unrelated names, low enough for a couple of typos
+/// ("Machdo de Asis" vs. "Machado de Assis" scores 0.6)
+pub const DEFAULT_FUZZY_THRESHOLD: f32 = 0.5;
+
+diesel::define_sql_function! {
+    /// pg_trgm: similarity between the trigrams of `a` and the most similar
+    /// run of words in `b`, from 0 to 1
+    fn word_similarity(a: Text, b: Text) -> Float4;
+}
+
+diesel::define_sql_function! {
+    /// `text` in lower case
+    fn lower(text: Text) -> Text;
+}
+
+// pg_trgm: `a <% b` holds when word_similarity(a, b) reaches
+// `pg_trgm.word_similarity_threshold`; unlike the function it can use the
+// trigram indexes
+diesel::infix_operator!(WordSimilarTo, " <% ", backend: diesel::pg::Pg);
+
+/// An author returned by fuzzy search, with the trigram word similarity
+/// between the query and its name
+#[derive(Serialize, Debug)]
+pub struct ScoredAuthorOutput {
+    #[serde(flatten)]
+    pub author: AuthorOutput,
+    pub score: f32,
+}
+
+/// A book returned by fuzzy title search, with the trigram word similarity
+/// between the query and its title
+#[derive(Serialize, Debug)]
+pub struct FuzzyBookOutput {
+    #[serde(flatten)]
+    pub book: BookOutput,
+    pub score: f32,
+}
+
+/// Sets the threshold of the `<%` operator for the current transaction
+fn set_word_similarity_threshold(conn: &mut PgConnection, threshold: f32) -> QueryResult<()> {
+    set_local(
+        conn,
+        "pg_trgm.word_similarity_threshold",
+        &threshold.to_string(),
+    )
+}
+
+/// Typo-tolerant author search: matches names containing `name` or whose
+/// trigram word similarity with it reaches `threshold`, one page at a time,
+/// best matches first unless `page` sorts otherwise
+pub fn fuzzy_search_authors(
+    conn: &mut PgConnection,
+    name: &str,
+    threshold: f32,
+    page: &PageRequest,
+) -> Result<Paginated<ScoredAuthorOutput>> {
+    let pattern = contains_pattern(name);
+    let matching = || {
+        live_authors().filter(
+            WordSimilarTo::new(name.into_sql::<Text>(), authors::name)
+                .or(authors::name.ilike(pattern.clone())),
+        )
+    };
+    let score =
+        || -> Boxed<'_, authors::table, Float4> { Box::new(word_similarity(name, authors::name)) };
+
+    let (authors, total) = conn
+        .transaction(|conn| {
+            set_word_similarity_threshold(conn, threshold)?;
+            let authors = page_authors(matching(), page, Some(&score))?
+                .select((Author::as_select(), word_similarity(name, authors::name)))
+                .load::<(Author, f32)>(conn)?;
+            let total = page
+                .with_total
+                .then(|| matching().count().get_result::<i64>(conn))
+                .transpose()?;
+            Ok::<_, Error>((authors, total))
+        })
+        .context("Failed to search authors by name")?;
+
+    Ok(
+        Paginated::from_rows(authors, page, total, |(author, score)| match page.sort {
+            SortBy::Relevance => (SortKey::Float(*score), author.id),
+            sort => author_position(author, sort),
+        })
+        .map(|(author, score)| ScoredAuthorOutput {
+            author: AuthorOutput::from(author),
+            score,
+        }),
+    )
+}
+
+/// Typo-tolerant title search: matches titles containing `title` or whose
+/// trigram word similarity with it reaches `threshold`, one page at a time,
+/// best matches first unless `page` sorts otherwise
+pub fn fuzzy_search_books_by_title(
+    conn: &mut PgConnection,
+    title: &str,
+    threshold: f32,
+    page: &PageRequest,
+) -> Result<Paginated<FuzzyBookOutput>> {
+    let pattern = contains_pattern(title);
+    let matching = || {
+        live_books().filter(
+            WordSimilarTo::new(title.into_sql::<Text>(), books::title)
+                .or(books::title.ilike(pattern.clone())),
+        )
+    };
+    let score =
+        || -> Boxed<'_, books::table, Float4> { Box::new(word_similarity(title, books::title)) };
+
+    let (books, total) = conn
+        .transaction(|conn| {
+            set_word_similarity_threshold(conn, threshold)?;
+            let books = page_books(matching(), page, Some(&score))?
+                .select((Book::as_select(), word_similarity(title, books::title)))
+                .load::<(Book, f32)>(conn)?;
+            let total = page
+                .with_total
+                .then(|| matching().count().get_result::<i64>(conn))
+                .transpose()?;
+            Ok::<_, Error>((books, total))
+        })
+        .context("Failed to search books by title")?;
+
+    Paginated::from_rows(books, page, total, |(book, score)| match page.sort {
+        SortBy::Relevance => (SortKey::Float(*score), book.id),
+        sort => book_position(book, sort),
+    })
+    .try_map_items(|rows| with_authors_paired(conn, rows))
+    .map(|page| page.map(|(book, score)| FuzzyBookOutput { book, score }))
+}
+
+/// A book returned by hybrid search, with its fused score and the rank it
+/// reached in each retriever (1-based; absent when it was not retrieved)
+#[derive(Serialize, Debug)]
+pub struct HybridBookOutput {
+    #[serde(flatten)]
+    pub book: BookOutput,
+    pub score: f32,
+    pub lexical_rank: Option<usize>,
+    pub vector_rank: Option<usize>,
+}
+
+/// Document searched by full-text queries; matches book_full_text_index
+const FULL_TEXT_DOCUMENT: &str =
+    "to_tsvector('portuguese', books.title || ' ' || books.abstract_text)";
+
+/// Full-text search over title and abstract with the `portuguese` text
+/// search configuration, restricted to books matching `filters`. Accepts
+/// web-search syntax (`"exact phrase"`, `or`, `-excluded`) and returns
+/// `(book id, rank)` ordered from best to worst.
+pub fn lexical_search(
+    conn: &mut PgConnection,
+    query_text: &str,
+    limit: i32,
+    filters: &SearchFilters,
+) -> Result<Vec<(i32, f32)>> {
+    let matches = sql::<Bool>(&format!(
+        "{} @@ websearch_to_tsquery('portuguese', ",
+        FULL_TEXT_DOCUMENT
+    ))
+    .bind::<Text, _>(query_text)
+    .sql(")");
+    let rank = || {
+        sql::<Float4>(&format!(
+            "ts_rank_cd({}, websearch_to_tsquery('portuguese', ",
+            FULL_TEXT_DOCUMENT
+        ))
+        .bind::<Text, _>(query_text)
+        .sql("))::real")
+    };
+
+    apply_filters(live_books(), filters)
+        .filter(matches)
+        .order((rank().desc(), books::id))
+        .limit(limit as i64)
+        .select((books::id, rank()))
+        .load::<(i32, f32)>(conn)
+        .context("Failed to execute full-text query")
+}
+
+/// Merges rankings with reciprocal rank fusion: each id scores the sum of
+/// `1 / (k + rank)` over the rankings it appears in. Returns ids ordered by
+/// fused score, ties broken by id.
+pub fn reciprocal_rank_fusion(rankings: &[&[i32]], k: f32) -> Vec<(i32, f32)> {
+    let mut scores: HashMap<i32, f32> = HashMap::new();
+    for ranking in rankings {
+        for (position, id) in ranking.iter().enumerate() {
+            *scores.entry(*id).or_insert(0.0) += 1.0 / (k + (position + 1) as f32);
+        }
+    }
+
+    let mut fused: Vec<(i32, f32)> = scores.into_iter().collect();
+    fused.sort_by(|(a, a_score), (b, b_score)| b_score.total_cmp(a_score).then_with(|| a.cmp(b)));
+    fused
+}
+
+/// Runs full-text and vector search for the same query and merges both with
+/// reciprocal rank fusion, so books matching the exact words and books close
+/// in meaning both surface. Both retrievers only consider books matching
+/// `filters`; `min_score` only applies to vector matches. Queries too short
+/// to embed fall back to the full-text results alone.
+pub fn hybrid_search(
+    conn: &mut PgConnection,
+    embedder: &dyn Embedder,
+    query_text: &str,
+    limit: i32,
+    min_score: Option<f32>,
+    filters: &SearchFilters,
+) -> Result<Vec<HybridBookOutput>> {
+    let candidates = (limit * CANDIDATES_PER_RESULT).min(MAX_CANDIDATES);
+
+    let lexical: Vec<i32> = lexical_search(conn, query_text, candidates, filters)?
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    let vector: Vec<i32> =
+        match nearest_to_prompt(conn, embedder, query_text, candidates, min_score, filters) {
+            Ok(results) => results.into_iter().map(|(book, _)| book.id).collect(),
+            // A query too short to embed still has its full-text matches
+            Err(Error::BadRequest(_)) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+    let mut fused = reciprocal_rank_fusion(&[&lexical, &vector], RRF_K);
+    fused.truncate(limit.max(0) as usize);
+
+    let ids: Vec<i32> = fused.iter().map(|(id, _)| *id).collect();
+    let mut books: HashMap<i32, Book> = live_books()
+        .filter(books::id.eq_any(&ids))
+        .select(Book::as_select())
+        .load(conn)
+        .context("Failed to load hybrid search results")?
+        .into_iter()
+        .map(|book| (book.id, book))
+        .collect();
+
+    // A book deleted between the queries is simply skipped
+    let rows: Vec<(Book, f32)> = fused
+        .into_iter()
+        .filter_map(|(id, score)| Some((books.remove(&id)?, score)))
+        .collect();
+
+    let rank_in = |ranking: &[i32], id: i32| ranking.iter().position(|&r| r == id).map(|p| p + 1);
+    Ok(with_authors_paired(conn, rows)?
+        .into_iter()
+        .map(|(book, score)| HybridBookOutput {
+            lexical_rank: rank_in(&lexical, book.id),
+            vector_rank: rank_in(&vector, book.id),
+            book,
+            score,
+        })
+        .collect())
+}
+
+/// Weights of the title and content criteria in the combined score of a
+/// multi-criteria search, when both are given
+const TITLE_WEIGHT: f32 = 0.5;
+const CONTENT_WEIGHT: f32 = 0.5;
+
+/// Predicates narrowing a search to some books; every field is optional
+/// and the given ones must all hold
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SearchFilters {
+    /// Written by one of these authors
+    #[serde(default, deserialize_with = "list_from_text_or_value")]
+    pub author_ids: Option<Vec<i32>>,
+    /// Some author's name contains this, or matches it with a word
+    /// similarity of at least [`DEFAULT_FUZZY_THRESHOLD`]
+    pub author_name: Option<String>,
+    /// Some author is from this country (case-insensitive)
+    pub author_country: Option<String>,
+    /// Published in or after this year
+    #[serde(default, deserialize_with = "from_text_or_value")]
+    pub year_from: Option<i32>,
+    /// Published in or before this year
+    #[serde(default, deserialize_with = "from_text_or_value")]
+    pub year_to: Option<i32>,
+}
+
+/// Criteria of a multi-criteria search. `title` and `content` rank the
+/// results; a search with neither returns matching books newest first.
+#[derive(Debug, Clone, Default)]
+pub struct SearchCriteria {
+    /// Fuzzy match against the title; also restricts results to matches
+    pub title: Option<String>,
+    /// Free text compared with each abstract through its embedding
+    pub content: Option<String>,
+    pub filters: SearchFilters,
+    /// Minimum trigram word similarity of fuzzy title matches
+    pub threshold: f32,
+    pub limit: i32,
+}
+
+/// A book returned by multi-criteria search. `score` combines the title and
+/// content scores present; all are absent when neither criterion was given.
+#[derive(Serialize, Debug)]
+pub struct MultiCriteriaBookOutput {
+    #[serde(flatten)]
+    pub book: BookOutput,
+    pub score: Option<f32>,
+    pub title_score: Option<f32>,
+    pub content_score: Option<f32>,
+}
+
+type BookScore<'a, ST> = Box<dyn BoxableExpression<books::table, Pg, SqlType = ST> + 'a>;
+
+/// Applies `filters` to a books query
+pub fn apply_filters<'a>(
+    mut query: books::BoxedQuery<'a, Pg>,
+    filters: &'a SearchFilters,
+) -> books::BoxedQuery<'a, Pg> {
+    if let Some(year_from) = filters.year_from {
+        query = query.filter(books::publication_year.ge(year_from));
+    }
+    if let Some(year_to) = filters.year_to {
+        query = query.filter(books::publication_year.le(year_to));
+    }
+
+    if let Some(author_ids) = &filters.author_ids {
+        query = query.filter(
+            books::id.eq_any(
+                books_authors::table
+                    .filter(books_authors::author_id.eq_any(author_ids))
+                    .select(books_authors::book_id),
+            ),
+        );
+    }
+
+    if filters.author_name.is_some() || filters.author_country.is_some() {
+        // Name and country must hold for the same author
+        let mut matching = books_authors::table
+            .inner_join(authors::table)
+            .filter(authors::deleted_at.is_null())
+            .select(books_authors::book_id)
+            .into_boxed();
+        if let Some(name) = &filters.author_name {
+            // Compared with the function rather than `<%`, so the match does
+            // not depend on the transaction's similarity threshold
+            matching = matching.filter(
+                authors::name
+                    .ilike(contains_pattern(name))
+                    .or(word_similarity(name.as_str(), authors::name).ge(DEFAULT_FUZZY_THRESHOLD)),
+            );
+        }
+        if let Some(country) = &filters.author_country {
+            matching = matching.filter(lower(authors::country).eq(lower(country.as_str())));
+        }
+        query = query.filter(books::id.eq_any(matching));
+    }
+
+    query
+}
+
+/// Searches books by any combination of title, content, author, author
+/// country and publication years, returning the `limit` best matches.
+///
+/// Filters become SQL predicates. When both title and content are given,
+/// the (at most [`MAX_CANDIDATES`]) best title matches are re-ranked by the
+/// weighted sum of their title and content scores.
+pub fn multi_criteria_search(
+    conn: &mut PgConnection,
+    embedder: &dyn Embedder,
+    criteria: &SearchCriteria,
+) -> Result<Vec<MultiCriteriaBookOutput>> {
+    let content_vector = match &criteria.content {
+        Some(content) => {
+            let embedding = embedder.embed(content)?;
+            if norm(&embedding) == 0.0 {
+                return Err(query_too_short());
+            }
+            Some(Vector::from(embedding))
+        }
+        None => None,
+    };
+    let title = criteria.title.as_deref();
+    let pattern = title.map(contains_pattern);
+
+    let title_score = || -> BookScore<Nullable<Float4>> {
+        match title {
+            Some(title) => Box::new(word_similarity(title, books::title).nullable()),
+            None => Box::new(sql::<Nullable<Float4>>("NULL")),
+        }
+    };
+    let distance = || -> BookScore<Nullable<Double>> {
+        match &content_vector {
+            Some(vector) => {
+                Box::new(embedding_with_dimension(embedder.dimension()).cosine_distance(vector))
+            }
+            None => Box::new(sql::<Nullable<Double>>("NULL")),
+        }
+    };
+
+    let mut query = apply_filters(live_books(), &criteria.filters);
+    if let (Some(title), Some(pattern)) = (title, &pattern) {
+        query = query.filter(
+            WordSimilarTo::new(title.into_sql::<Text>(), books::title)
+                .or(books::title.ilike(pattern)),
+        );
+    }
+    if content_vector.is_some() {
+        query = query
+            .filter(books::embedding_version.eq(embedder.version()))
+            .filter(embedding_has_dimension(embedder.dimension()))
+            .filter(embedding_is_nonzero());
+    }
+    query = match (title, &content_vector) {
+        (Some(_), Some(_)) => query
+            .order((title_score().desc(), books::id))
+            .limit(MAX_CANDIDATES as i64),
+        (Some(_), None) => query
+            .order((title_score().desc(), books::id))
+            .limit(criteria.limit as i64),
+        // Ordering by the distance alone lets the HNSW index serve the query
+        (None, Some(_)) => query.order(distance()).limit(criteria.limit as i64),
+        (None, None) => query
+            .order((books::publication_year.desc(), books::id))
+            .limit(criteria.limit as i64),
+    };
+
+    let rows = conn
+        .transaction(|conn| {
+            set_word_similarity_threshold(conn, criteria.threshold)?;
+            if content_vector.is_some() {
+                prepare_hnsw_scan(conn, criteria.limit)?;
+            }
+            query
+                .select((Book::as_select(), title_score(), distance()))
+                .load::<(Book, Option<f32>, Option<f64>)>(conn)
+        })
+        .context("Failed to execute multi-criteria search")?;
+
+    // (book, (score, title score, content score)); a NaN distance would
+    // sort first, so such rows are left out
+    let mut scored: Vec<_> = rows
+        .into_iter()
+        .filter(|(_, _, distance)| distance.is_none_or(f64::is_finite))
+        .map(|(book, title_score, distance)| {
+            let content_score = distance.map(|d| 1.0 - d as f32);
+            let score = match (title_score, content_score) {
+                (Some(t), Some(c)) => Some(TITLE_WEIGHT * t + CONTENT_WEIGHT * c),
+                (t, c) => t.or(c),
+            };
+            (book, (score, title_score, content_score))
+        })
+        .collect();
+
+    // Re-rank title matches by the combined score, and put the roughly
+    // ordered rows of an iterative index scan back in order
+    if content_vector.is_some() {
+        scored.sort_by(|(a, (a_score, ..)), (b, (b_score, ..))| {
+            b_score
+                .unwrap_or(0.0)
+                .total_cmp(&a_score.unwrap_or(0.0))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        scored.truncate(criteria.limit.max(0) as usize);
+    }
+
+    Ok(with_authors_paired(conn, scored)?
+        .into_iter()
+        .map(
+            |(book, (score, title_score, content_score))| MultiCriteriaBookOutput {
+                book,
+                score,
+                title_score,
+                content_score,
+            },
+        )
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::initialization::establish_connection;
+    use crate::database::insertion::{insert_author, insert_book, link_book_author};
+    use crate::embedding::HashingEmbedder;
+    use crate::models::{NewAuthor, NewBook};
+
+    // Helper function to check if database is available
+    fn db_available() -> bool {
+        std::env::var("DATABASE_URL").is_ok()
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let fused = reciprocal_rank_fusion(&[&[1, 2, 3], &[3, 1, 4]], RRF_K);
+        let ids: Vec<i32> = fused.iter().map(|(id, _)| *id).collect();
+        // 1 and 3 appear in both rankings; 1 ranks higher on average
+        assert_eq!(ids, vec![1, 3, 2, 4]);
+        assert!((fused[0].1 - (1.0 / 61.0 + 1.0 / 62.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion_empty() {
+        assert!(reciprocal_rank_fusion(&[&[], &[]], RRF_K).is_empty());
+        let fused = reciprocal_rank_fusion(&[&[7], &[]], RRF_K);
+        assert_eq!(fused, vec![(7, 1.0 / 61.0)]);
+    }
+
+    #[test]
+    fn test_hybrid_search() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+
+        let new_book = NewBook::new(
+            "Hybrid Search Test Book",
+            2025,
+            "Um faroleiro solitário observa as tempestades do litoral",
+            &embedder,
+        )
+        .expect("Failed to create book");
+        let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+
+        let lexical = lexical_search(&mut conn, "faroleiros", 10, &SearchFilters::default())
+            .expect("Failed to search");
+        assert!(lexical.iter().any(|(id, _)| *id == book.id));
+
+        let results = hybrid_search(
+            &mut conn,
+            &embedder,
+            "faroleiro tempestades",
+            10,
+            None,
+            &SearchFilters::default(),
+        )
+        .expect("Failed to run hybrid search");
+        let hit = results
+            .iter()
+            .find(|r| r.book.id == book.id)
+            .expect("Test book should be found");
+        assert!(hit.lexical_rank.is_some());
+
+        // Too short to embed, so only full-text matches can come back
+        let results = hybrid_search(
+            &mut conn,
+            &embedder,
+            "as",
+            10,
+            None,
+            &SearchFilters::default(),
+        )
+        .expect("Failed to run hybrid search");
+        assert!(results.iter().all(|r| r.vector_rank.is_none()));
+
+        // Cleanup
+        diesel::delete(books::table.filter(books::id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_fuzzy_search_authors() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let author = insert_author(
+            &mut conn,
+            &NewAuthor::new("Joaquim Fuzzyno Machadinho", 1839, "Brazil"),
+        )
+        .expect("Failed to insert author");
+
+        // Misspelled, still found
+        let best_first = PageRequest::first(100, SortBy::Relevance);
+        let results = fuzzy_search_authors(
+            &mut conn,
+            "Fuzino Machadino",
+            DEFAULT_FUZZY_THRESHOLD,
+            &best_first,
+        )
+        .expect("Failed to search authors")
+        .items;
+        let hit = results
+            .iter()
+            .find(|r| r.author.id == author.id)
+            .expect("Test author should be found");
+        assert!(hit.score >= DEFAULT_FUZZY_THRESHOLD);
+        assert!(
+            results
+                .windows(2)
+                .all(|pair| pair[0].score >= pair[1].score)
+        );
+
+        // Following cursors one author at a time yields the same ranking
+        let mut page = PageRequest {
+            limit: 1,
+            ..best_first.clone()
+        };
+        let mut visited = Vec::new();
+        loop {
+            let results = fuzzy_search_authors(
+                &mut conn,
+                "Fuzino Machadino",
+                DEFAULT_FUZZY_THRESHOLD,
+                &page,
+            )
+            .expect("Failed to search authors");
+            visited.extend(results.items.iter().map(|r| r.author.id));
+            match results.next_cursor {
+                Some(cursor) => page.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        let expected: Vec<i32> = results.iter().map(|r| r.author.id).collect();
+        assert_eq!(visited, expected);
+
+        // A stricter threshold drops the misspelling
+        let results = fuzzy_search_authors(&mut conn, "Fuzino Machadino", 0.99, &best_first)
+            .expect("Failed to search authors");
+        assert!(results.items.iter().all(|r| r.author.id != author.id));
+
+        // Cleanup
+        diesel::delete(authors::table.filter(authors::id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_fuzzy_search_books_by_title() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let new_book = NewBook::new(
+            "Memorias Fuzzyticas de um Sargento",
+            1854,
+            "A fuzzy title search test book",
+            &HashingEmbedder::new(),
+        )
+        .expect("Failed to create book");
+        let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+
+        let results = fuzzy_search_books_by_title(
+            &mut conn,
+            "Memorias Fuzyticas",
+            DEFAULT_FUZZY_THRESHOLD,
+            &PageRequest::first(100, SortBy::Relevance),
+        )
+        .expect("Failed to search books");
+        assert!(results.items.iter().any(|r| r.book.id == book.id));
+
+        // Cleanup
+        diesel::delete(books::table.filter(books::id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_multi_criteria_search_skips_zero_vectors() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+        let mut new_book = NewBook::new(
+            "Zero Vector Multicriteria Book",
+            2025,
+            "A zero vector among the rivers",
+            &embedder,
+        )
+        .expect("Failed to create book");
+        new_book.embedding = Some(Vector::from(vec![0.0; embedder.dimension()]));
+        let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+
+        let criteria = SearchCriteria {
+            content: Some("a zero vector among the rivers".to_string()),
+            threshold: DEFAULT_FUZZY_THRESHOLD,
+            limit: 100,
+            ..SearchCriteria::default()
+        };
+        let results = multi_criteria_search(&mut conn, &embedder, &criteria).unwrap();
+        assert!(results.iter().all(|r| r.book.id != book.id));
+        assert!(results.iter().all(|r| r.score.is_some_and(f32::is_finite)));
+
+        // Cleanup
+        diesel::delete(books::table.filter(books::id.eq(book.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+
+    #[test]
+    fn test_multi_criteria_search() {
+        if !db_available() {
+            return;
+        }
+
+        let mut conn = establish_connection().expect("Failed to connect to database");
+        let embedder = HashingEmbedder::new();
+
+        let author = insert_author(
+            &mut conn,
+            &NewAuthor::new("Multicriteria Test Author", 1850, "Multicriterland"),
+        )
+        .expect("Failed to insert author");
+        let mut books_ids = Vec::new();
+        for (title, year) in [
+            ("Multicriteria Old Book", 1880),
+            ("Multicriteria New Book", 1990),
+        ] {
+            let new_book = NewBook::new(title, year, "A story about rivers and bridges", &embedder)
+                .expect("Failed to create book");
+            let book = insert_book(&mut conn, &new_book).expect("Failed to insert book");
+            link_book_author(&mut conn, book.id, author.id).unwrap();
+            books_ids.push(book.id);
+        }
+
+        let criteria =
+            |title: Option<&str>, content: Option<&str>, year_to: Option<i32>| SearchCriteria {
+                title: title.map(str::to_string),
+                content: content.map(str::to_string),
+                filters: SearchFilters {
+                    author_country: Some("multicriterland".to_string()),
+                    year_to,
+                    ..SearchFilters::default()
+                },
+                threshold: DEFAULT_FUZZY_THRESHOLD,
+                limit: 10,
+            };
+
+        // Filters only: newest first, no scores
+        let results = multi_criteria_search(&mut conn, &embedder, &criteria(None, None, None))
+            .expect("Failed to search");
+        let ids: Vec<i32> = results.iter().map(|r| r.book.id).collect();
+        assert_eq!(ids, vec![books_ids[1], books_ids[0]]);
+        assert!(results.iter().all(|r| r.score.is_none()));
+
+        // Year range
+        let results =
+            multi_criteria_search(&mut conn, &embedder, &criteria(None, None, Some(1900)))
+                .expect("Failed to search");
+        let ids: Vec<i32> = results.iter().map(|r| r.book.id).collect();
+        assert_eq!(ids, vec![books_ids[0]]);
+
+        // Title and content together produce a combined score
+        let results = multi_criteria_search(
+            &mut conn,
+            &embedder,
+            &criteria(Some("Multicriteria New"), Some("rivers and bridges"), None),
+        )
+        .expect("Failed to search");
+        assert_eq!(results[0].book.id, books_ids[1]);
+        assert!(results[0].title_score.is_some() && results[0].content_score.is_some());
+
+        // Cleanup
+        diesel::delete(books_authors::table.filter(books_authors::author_id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(books::table.filter(books::id.eq_any(&books_ids)))
+            .execute(&mut conn)
+            .ok();
+        diesel::delete(authors::table.filter(authors::id.eq(author.id)))
+            .execute(&mut conn)
+            .ok();
+    }
+}
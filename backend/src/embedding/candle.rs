@@ -0,0 +1,222 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config as BertConfig, DTYPE};
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+use super::Embedder;
+use super::hashing::{FNV_OFFSET_BASIS, fnv1a_64, fnv1a_64_from};
+
+const CONFIG_FILE: &str = "config.json";
+const TOKENIZER_FILE: &str = "tokenizer.json";
+const WEIGHTS_FILE: &str = "model.safetensors";
+
+/// Embeds text with a BERT-style sentence-transformer running on the CPU.
+/// Token embeddings are mean-pooled over the attention mask and
+/// L2-normalized, as sentence-transformers does for models such as
+/// `all-MiniLM-L6-v2` or `paraphrase-multilingual-MiniLM-L12-v2`.
+pub struct CandleEmbedder {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    dimension: usize,
+    version: String,
+}
+
+impl CandleEmbedder {
+    pub const NAME: &'static str = "candle";
+
+    /// Loads a model from a local directory holding `config.json`,
+    /// `tokenizer.json` and `model.safetensors`
+    pub fn load(model_dir: &Path) -> Result<Self> {
+        let file = |name: &str| -> Result<PathBuf> {
+            let path = model_dir.join(name);
+            if path.is_file() {
+                Ok(path)
+            } else {
+                Err(anyhow::anyhow!("Missing model file {}", path.display()))
+            }
+        };
+        let config_path = file(CONFIG_FILE)?;
+        let tokenizer_path = file(TOKENIZER_FILE)?;
+        let weights_path = file(WEIGHTS_FILE)?;
+
+        let config_bytes = std::fs::read(&config_path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", config_path.display(), e))?;
+        let config: BertConfig = serde_json::from_slice(&config_bytes).map_err(|e| {
+            anyhow::anyhow!("Invalid model config {}: {}", config_path.display(), e)
+        })?;
+
+        let mut tokenizer = Tokenizer::from_file(&tokenizer_path).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to load tokenizer {}: {}",
+                tokenizer_path.display(),
+                e
+            )
+        })?;
+        tokenizer.with_padding(Some(PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_position_embeddings,
+                ..TruncationParams::default()
+            }))
+            .map_err(|e| anyhow::anyhow!("Failed to configure tokenizer truncation: {}", e))?;
+
+        // SAFETY: the weights file is memory-mapped read-only and must not
+        // be modified while the server runs
+        let vb =
+            unsafe { VarBuilder::from_mmaped_safetensors(&[&weights_path], DTYPE, &Device::Cpu) }
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", weights_path.display(), e))?;
+        let model = BertModel::load(vb, &config)
+            .map_err(|e| anyhow::anyhow!("Failed to build model: {}", e))?;
+
+        // Fingerprint every file, so swapping in a fine-tuned model with the
+        // same architecture still changes the version
+        let mut fingerprint = config_bytes;
+        for path in [&tokenizer_path, &weights_path] {
+            let hash = fnv1a_64_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+            fingerprint.extend_from_slice(&hash.to_le_bytes());
+        }
+        let model_name = model_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "model".to_string());
+        let version = format!(
+            "candle-{}-{}-{:016x}",
+            model_name,
+            config.hidden_size,
+            fnv1a_64(&fingerprint)
+        );
+
+        Ok(CandleEmbedder {
+            model,
+            tokenizer,
+            dimension: config.hidden_size,
+            version,
+        })
+    }
+
+    fn forward(&self, texts: &[&str]) -> candle_core::Result<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(candle_core::Error::msg)?;
+
+        let device = Device::Cpu;
+        let stack = |field: fn(&tokenizers::Encoding) -> &[u32]| -> candle_core::Result<Tensor> {
+            let rows = encodings
+                .iter()
+                .map(|encoding| Tensor::new(field(encoding), &device))
+                .collect::<candle_core::Result<Vec<_>>>()?;
+            Tensor::stack(&rows, 0)
+        };
+        let input_ids = stack(tokenizers::Encoding::get_ids)?;
+        let type_ids = stack(tokenizers::Encoding::get_type_ids)?;
+        let attention_mask = stack(tokenizers::Encoding::get_attention_mask)?;
+
+        // (batch, tokens, hidden)
+        let hidden = self
+            .model
+            .forward(&input_ids, &type_ids, Some(&attention_mask))?;
+
+        // Mean over the real (non-padding) tokens of each text
+        let mask = attention_mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+        let summed = hidden.broadcast_mul(&mask)?.sum(1)?;
+        let counts = mask.sum(1)?.clamp(1e-9, f64::MAX)?;
+        let pooled = summed.broadcast_div(&counts)?;
+
+        let norms = pooled
+            .sqr()?
+            .sum_keepdim(1)?
+            .sqrt()?
+            .clamp(1e-12, f64::MAX)?;
+        pooled.broadcast_div(&norms)?.to_vec2::<f32>()
+    }
+}
+
+/// FNV-1a hash of a file, read in chunks: the weights of a model can take
+/// hundreds of megabytes
+fn fnv1a_64_file(path: &Path) -> std::io::Result<u64> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut buffer = [0u8; 64 * 1024];
+    let mut hash = FNV_OFFSET_BASIS;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hash);
+        }
+        hash = fnv1a_64_from(hash, &buffer[..read]);
+    }
+}
+
+impl Embedder for CandleEmbedder {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        Ok(self.embed_batch(&[text])?.remove(0))
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.forward(texts)
+            .map_err(|e| anyhow::anyhow!("Failed to compute embeddings: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_model() {
+        let result = CandleEmbedder::load(Path::new("/nonexistent/model"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_file_hash_matches_in_memory_hash() {
+        // Spans several chunks, the last one partial
+        let bytes: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let path =
+            std::env::temp_dir().join(format!("icarusp-candle-test-{}.bin", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let hash = fnv1a_64_file(&path);
+        std::fs::remove_file(&path).ok();
+        assert_eq!(hash.unwrap(), fnv1a_64(&bytes));
+    }
+
+    // Runs against a real model when CANDLE_MODEL_DIR points at one
+    #[test]
+    fn test_embeddings_are_normalized() {
+        let Ok(model_dir) = std::env::var("CANDLE_MODEL_DIR") else {
+            return;
+        };
+
+        let embedder = CandleEmbedder::load(Path::new(&model_dir)).unwrap();
+        let embeddings = embedder
+            .embed_batch(&["O ciúme de Bentinho", "Um pescador e o mar"])
+            .unwrap();
+        assert_eq!(embeddings.len(), 2);
+        for embedding in embeddings {
+            assert_eq!(embedding.len(), embedder.dimension());
+            let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert!((norm - 1.0).abs() < 1e-4);
+        }
+    }
+}
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::embedding::embedding::{compute_embedding, compute_cosine_similarity};
+    use crate::embedding::compute_embedding;
+    use crate::embedding::vector::compute_cosine_similarity;
 
     #[test]
     fn test_embedding_length() {
@@ -53,4 +53,4 @@ mod tests {
         // Similarity should be between -1 and 1
         assert!(sims[0].0 <= 1.0 && sims[0].0 >= -1.0);
     }
-}
\ No newline at end of file
+}
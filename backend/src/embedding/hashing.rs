@@ -0,0 +1,511 @@
+use anyhow::Result;
+
+use super::Embedder;
+use super::normalize::{NormalizationOptions, normalize};
+
+/// 64-bit FNV-1a offset basis and prime, as published at
+/// <http://www.isthe.com/chongo/tech/comp/fnv/>.
+pub(super) const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Prefix that keeps word n-gram features apart from character n-grams
+/// with the same spelling (the word "de" vs. the character bigram "de").
+const WORD_FEATURE_PREFIX: &[u8] = b"w\0";
+
+/// Offset basis of the second FNV-1a hash, which picks the sign of a
+/// feature independently of its bucket
+const SIGN_HASH_BASIS: u64 = 0x84222325cbf29ce4;
+
+/// 64-bit FNV-1a hash. Unlike `std`'s `DefaultHasher`, its output is fixed
+/// by specification, so stored vectors survive toolchain upgrades.
+pub fn fnv1a_64(bytes: &[u8]) -> u64 {
+    fnv1a_64_from(FNV_OFFSET_BASIS, bytes)
+}
+
+/// FNV-1a starting from `basis` instead of the standard offset basis
+pub(super) fn fnv1a_64_from(basis: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(basis, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Inclusive range of n-gram lengths
+pub type NgramRange = (usize, usize);
+
+/// Parses an n-gram range such as `3`, `2-5` or `none` (disabled)
+pub fn parse_ngram_range(value: &str) -> Result<Option<NgramRange>> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+
+    let (min, max) = match value.split_once('-') {
+        Some((min, max)) => (min.trim(), max.trim()),
+        None => (value, value),
+    };
+    let min = min
+        .parse::<usize>()
+        .map_err(|e| anyhow::anyhow!("Invalid n-gram range {:?}: {}", value, e))?;
+    let max = max
+        .parse::<usize>()
+        .map_err(|e| anyhow::anyhow!("Invalid n-gram range {:?}: {}", value, e))?;
+    if min == 0 || min > max {
+        return Err(anyhow::anyhow!(
+            "Invalid n-gram range {:?}: expected 1 <= min <= max",
+            value
+        ));
+    }
+
+    Ok(Some((min, max)))
+}
+
+/// Parameters of the [`HashingEmbedder`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HashingOptions {
+    /// Number of hash buckets, i.e. the length of every vector
+    pub dimension: usize,
+    /// Character n-gram lengths to extract, if any
+    pub char_ngrams: Option<NgramRange>,
+    /// Word n-gram lengths to extract, if any
+    pub word_ngrams: Option<NgramRange>,
+    /// Give each feature a sign from a second hash, so colliding features
+    /// tend to cancel out instead of piling up in the same bucket
+    pub signed: bool,
+    /// Replace raw counts `tf` with `1 + ln(tf)`
+    pub sublinear_tf: bool,
+    /// Text normalization applied before extracting n-grams
+    pub normalization: NormalizationOptions,
+}
+
+impl Default for HashingOptions {
+    /// Unsigned, raw-count character trigrams in 512 buckets
+    fn default() -> Self {
+        HashingOptions {
+            dimension: 512,
+            char_ngrams: Some((3, 3)),
+            word_ngrams: None,
+            signed: false,
+            sublinear_tf: false,
+            normalization: NormalizationOptions::default(),
+        }
+    }
+}
+
+impl HashingOptions {
+    /// Checks the options describe a usable embedder
+    pub fn validate(&self) -> Result<()> {
+        if self.dimension == 0 {
+            return Err(anyhow::anyhow!("Embedding dimension must be at least 1"));
+        }
+        if self.char_ngrams.is_none() && self.word_ngrams.is_none() {
+            return Err(anyhow::anyhow!(
+                "At least one of character or word n-grams must be enabled"
+            ));
+        }
+        for (min, max) in self.char_ngrams.iter().chain(self.word_ngrams.iter()) {
+            if *min == 0 || min > max {
+                return Err(anyhow::anyhow!(
+                    "Invalid n-gram range {}-{}: expected 1 <= min <= max",
+                    min,
+                    max
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Identifier of every parameter that affects the produced vectors
+    fn version(&self) -> String {
+        fn range(prefix: &str, (min, max): NgramRange) -> String {
+            if min == max {
+                format!("{}{}", prefix, min)
+            } else {
+                format!("{}{}to{}", prefix, min, max)
+            }
+        }
+
+        let mut parts = vec!["hashing".to_string()];
+        if let Some(chars) = self.char_ngrams {
+            parts.push(range("char", chars));
+        }
+        if let Some(words) = self.word_ngrams {
+            parts.push(range("word", words));
+        }
+        if self.signed {
+            parts.push("signhash".to_string());
+        }
+        if self.sublinear_tf {
+            parts.push("logtf".to_string());
+        }
+        parts.push("fnv1a".to_string());
+        parts.push(self.dimension.to_string());
+        parts.push(self.normalization.tag());
+        parts.push("v3".to_string());
+        parts.join("-")
+    }
+}
+
+/// Embeds text by normalizing it, hashing its character and/or word n-grams
+/// (FNV-1a) into a fixed number of buckets, then L2-normalizing the result.
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    options: HashingOptions,
+    version: String,
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HashingEmbedder {
+    pub const NAME: &'static str = "hashing";
+
+    /// Creates an embedder with the default options
+    pub fn new() -> Self {
+        HashingEmbedder {
+            options: HashingOptions::default(),
+            version: HashingOptions::default().version(),
+        }
+    }
+
+    /// Creates an embedder with the default options, but normalizing text
+    /// with `normalization` first
+    pub fn with_normalization(normalization: NormalizationOptions) -> Self {
+        Self::with_options(HashingOptions {
+            normalization,
+            ..HashingOptions::default()
+        })
+        .expect("default hashing options are valid")
+    }
+
+    /// Creates an embedder with custom options
+    pub fn with_options(options: HashingOptions) -> Result<Self> {
+        options.validate()?;
+        Ok(HashingEmbedder {
+            options,
+            version: options.version(),
+        })
+    }
+
+    pub fn options(&self) -> &HashingOptions {
+        &self.options
+    }
+
+    /// Adds one occurrence of `feature` to its bucket
+    fn add_feature(&self, embedding: &mut [f32], feature: &[u8]) {
+        let hash = fnv1a_64(feature);
+        // The modulo is taken on the `u64` hash so the bucket is the same on
+        // 32- and 64-bit targets. The sign comes from a second hash: a bit
+        // of the first one would be correlated with the bucket unless the
+        // dimension is a power of two.
+        let idx = (hash % self.options.dimension as u64) as usize;
+        let sign = if self.options.signed && fnv1a_64_from(SIGN_HASH_BASIS, feature) >> 63 == 1 {
+            -1.0
+        } else {
+            1.0
+        };
+        embedding[idx] += sign;
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn dimension(&self) -> usize {
+        self.options.dimension
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        // Initialize an embedding vector with zeros.
+        // The size of the vector is determined by the number of buckets.
+        let mut embedding = vec![0f32; self.options.dimension];
+        let normalized = normalize(text, &self.options.normalization);
+
+        // Character n-grams are taken over characters, not bytes, so
+        // multibyte characters are never split mid-codepoint.
+        if let Some((min, max)) = self.options.char_ngrams {
+            let chars: Vec<char> = normalized.chars().collect();
+            let mut ngram = String::new();
+            for n in min..=max {
+                for window in chars.windows(n) {
+                    ngram.clear();
+                    ngram.extend(window);
+                    self.add_feature(&mut embedding, ngram.as_bytes());
+                }
+            }
+        }
+
+        if let Some((min, max)) = self.options.word_ngrams {
+            let words: Vec<&str> = normalized.split_whitespace().collect();
+            let mut feature = Vec::new();
+            for n in min..=max {
+                for window in words.windows(n) {
+                    feature.clear();
+                    feature.extend_from_slice(WORD_FEATURE_PREFIX);
+                    feature.extend_from_slice(window.join(" ").as_bytes());
+                    self.add_feature(&mut embedding, &feature);
+                }
+            }
+        }
+
+        if self.options.sublinear_tf {
+            for v in &mut embedding {
+                if *v != 0.0 {
+                    *v = v.signum() * (1.0 + v.abs().ln());
+                }
+            }
+        }
+
+        // Compute the L2 norm (Euclidean norm) of the embedding vector.
+        // This is done by summing the squares of each element, then taking the
+        // square root.
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            for v in &mut embedding {
+                *v /= norm;
+            }
+        }
+        Ok(embedding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    #[test]
+    fn test_ngram_hash_consistency() {
+        let ngram = b"abc";
+        let idx1 = fnv1a_64(ngram) % 512;
+        let idx2 = fnv1a_64(ngram) % 512;
+        assert_eq!(idx1, idx2);
+    }
+
+    #[test]
+    fn test_fnv1a_reference_values() {
+        assert_eq!(fnv1a_64(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a_64(b"foobar"), 0x85944171f73967e8);
+    }
+
+    // Golden vectors: if these change, stored embeddings are no longer
+    // comparable and the embedder version must be bumped.
+    fn assert_golden(text: &str, expected: &[(usize, f32)]) {
+        let embedding = HashingEmbedder::new().embed(text).unwrap();
+        let nonzero: Vec<(usize, f32)> = embedding
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v != 0.0)
+            .map(|(i, v)| (i, *v))
+            .collect();
+        assert_eq!(nonzero.len(), expected.len(), "{:?}", nonzero);
+        for ((index, value), (expected_index, expected_value)) in nonzero.iter().zip(expected) {
+            assert_eq!(index, expected_index);
+            assert!((value - expected_value).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_golden_vectors() {
+        assert_golden("abcd", &[(98, FRAC_1_SQRT_2), (331, FRAC_1_SQRT_2)]);
+        assert_golden(
+            "abcabcabc",
+            &[(145, 0.485071), (329, 0.485071), (331, 0.727607)],
+        );
+        assert_golden(
+            "Dom Casmurro",
+            &[
+                (14, 0.316228),
+                (49, 0.316228),
+                (79, 0.316228),
+                (283, 0.316228),
+                (299, 0.316228),
+                (344, 0.316228),
+                (352, 0.316228),
+                (380, 0.316228),
+                (383, 0.316228),
+                (478, 0.316228),
+            ],
+        );
+        assert_golden("Ciúme", &[(266, 0.57735), (326, 0.57735), (508, 0.57735)]);
+    }
+
+    #[test]
+    fn test_embedding_ignores_accents_and_case() {
+        let embedder = HashingEmbedder::new();
+        assert_eq!(
+            embedder.embed("Ciúme").unwrap(),
+            embedder.embed("ciume").unwrap()
+        );
+        assert_eq!(
+            embedder.embed("Dom Casmurro").unwrap(),
+            embedder.embed("dom   CASMURRO!").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_ngrams_are_taken_over_characters() {
+        // "ção" is three characters but five bytes: a single trigram
+        let embedder = HashingEmbedder::with_normalization(NormalizationOptions::none());
+        let embedding = embedder.embed("ção").unwrap();
+        assert_eq!(embedding.iter().filter(|&&v| v != 0.0).count(), 1);
+    }
+
+    #[test]
+    fn test_default_version_is_stable() {
+        assert_eq!(
+            HashingEmbedder::new().version(),
+            "hashing-char3-fnv1a-512-nfkc+fold+noacc+punct-v3"
+        );
+    }
+
+    #[test]
+    fn test_parse_ngram_range() {
+        assert_eq!(parse_ngram_range("3").unwrap(), Some((3, 3)));
+        assert_eq!(parse_ngram_range("2-5").unwrap(), Some((2, 5)));
+        assert_eq!(parse_ngram_range("none").unwrap(), None);
+        assert!(parse_ngram_range("0-2").is_err());
+        assert!(parse_ngram_range("5-2").is_err());
+        assert!(parse_ngram_range("a").is_err());
+    }
+
+    #[test]
+    fn test_invalid_options_are_rejected() {
+        let no_features = HashingOptions {
+            char_ngrams: None,
+            word_ngrams: None,
+            ..HashingOptions::default()
+        };
+        assert!(HashingEmbedder::with_options(no_features).is_err());
+
+        let no_dimension = HashingOptions {
+            dimension: 0,
+            ..HashingOptions::default()
+        };
+        assert!(HashingEmbedder::with_options(no_dimension).is_err());
+    }
+
+    fn mixed_options() -> HashingOptions {
+        HashingOptions {
+            dimension: 1024,
+            char_ngrams: Some((2, 5)),
+            word_ngrams: Some((1, 2)),
+            signed: true,
+            sublinear_tf: true,
+            ..HashingOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_mixed_ngrams_dimension_and_norm() {
+        let embedder = HashingEmbedder::with_options(mixed_options()).unwrap();
+        let embedding = embedder
+            .embed("Um clássico da literatura brasileira.")
+            .unwrap();
+        assert_eq!(embedding.len(), 1024);
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+        // Signed hashing produces negative components
+        assert!(embedding.iter().any(|&v| v < 0.0));
+        assert_eq!(
+            embedder.version(),
+            "hashing-char2to5-word1to2-signhash-logtf-fnv1a-1024-nfkc+fold+noacc+punct-v3"
+        );
+    }
+
+    #[test]
+    fn test_signs_are_independent_of_buckets() {
+        // 384 is not a power of two, so the top bit of the bucket hash
+        // would be tied to the bucket
+        let embedder = HashingEmbedder::with_options(HashingOptions {
+            dimension: 384,
+            signed: true,
+            ..HashingOptions::default()
+        })
+        .unwrap();
+        let mut signs = vec![(0usize, 0usize); 384];
+        for i in 0..20_000 {
+            let mut embedding = vec![0f32; 384];
+            embedder.add_feature(&mut embedding, format!("feature {}", i).as_bytes());
+            let (bucket, value) = embedding
+                .iter()
+                .enumerate()
+                .find(|(_, v)| **v != 0.0)
+                .unwrap();
+            if *value > 0.0 {
+                signs[bucket].0 += 1;
+            } else {
+                signs[bucket].1 += 1;
+            }
+        }
+        let (positive, negative) = signs
+            .iter()
+            .fold((0, 0), |(p, n), (bp, bn)| (p + bp, n + bn));
+        assert!(positive.abs_diff(negative) < 1_000);
+        assert!(signs.iter().all(|&(p, n)| p > 0 && n > 0));
+    }
+
+    #[test]
+    fn test_word_ngrams_only() {
+        let embedder = HashingEmbedder::with_options(HashingOptions {
+            char_ngrams: None,
+            word_ngrams: Some((1, 1)),
+            ..HashingOptions::default()
+        })
+        .unwrap();
+        // Two-letter words are too short for trigrams but still count as words
+        let embedding = embedder.embed("ab").unwrap();
+        assert_eq!(embedding.iter().filter(|&&v| v != 0.0).count(), 1);
+        // Word order does not matter for unigrams
+        assert_eq!(
+            embedder.embed("gato preto").unwrap(),
+            embedder.embed("preto gato").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sublinear_tf_dampens_repetition() {
+        let raw = HashingEmbedder::new();
+        let sublinear = HashingEmbedder::with_options(HashingOptions {
+            sublinear_tf: true,
+            ..HashingOptions::default()
+        })
+        .unwrap();
+        // "abcabcabc" repeats "abc" three times and the other trigrams twice
+        let max = |v: Vec<f32>| v.into_iter().fold(0f32, f32::max);
+        assert!(max(sublinear.embed("abcabcabc").unwrap()) < max(raw.embed("abcabcabc").unwrap()));
+    }
+
+    #[test]
+    fn test_version_reflects_normalization() {
+        let default = HashingEmbedder::new();
+        let raw = HashingEmbedder::with_normalization(NormalizationOptions::none());
+        assert_ne!(default.version(), raw.version());
+    }
+
+    #[test]
+    fn test_embed_batch_matches_embed() {
+        let embedder = HashingEmbedder::new();
+        let batch = embedder
+            .embed_batch(&["gato preto", "violão azul"])
+            .unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0], embedder.embed("gato preto").unwrap());
+        assert_eq!(batch[1], embedder.embed("violão azul").unwrap());
+    }
+
+    #[test]
+    fn test_dimension_matches_output() {
+        let embedder = HashingEmbedder::new();
+        assert_eq!(embedder.embed("abcd").unwrap().len(), embedder.dimension());
+    }
+}
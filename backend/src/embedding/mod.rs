@@ -1,78 +1,147 @@
+use std::path::Path;
+use std::sync::Arc;
+
 use anyhow::Result;
 
-/// N-gram size and vocabulary size constants.
-const N_GRAM: usize = 3;
-const VOCAB_SIZE: usize = 512;
-
-/// Simple hash function for n-grams to map to vocabulary indices.
-fn ngram_hash(ngram: &[u8]) -> usize {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::Hasher;
-    let mut hasher = DefaultHasher::new();
-    hasher.write(ngram);
-    (hasher.finish() as usize) % VOCAB_SIZE
+use crate::Config;
+
+#[cfg(feature = "candle")]
+mod candle;
+#[cfg(test)]
+mod embedding_test;
+mod hashing;
+pub mod normalize;
+mod tfidf;
+pub mod vector;
+
+#[cfg(feature = "candle")]
+pub use candle::CandleEmbedder;
+pub use hashing::{HashingEmbedder, HashingOptions, NgramRange, parse_ngram_range};
+pub use normalize::NormalizationOptions;
+pub use tfidf::{TfidfEmbedder, TfidfOptions};
+
+/// Turns text into fixed-size float vectors stored in `books.embedding`.
+///
+/// Implementations must be deterministic: the same text, embedder and
+/// version always produce the same vector, otherwise stored vectors stop
+/// being comparable with query vectors.
+pub trait Embedder: Send + Sync {
+    /// Short identifier used to select this embedder in configuration
+    fn name(&self) -> &str;
+
+    /// Length of every vector returned by [`Embedder::embed`]
+    fn dimension(&self) -> usize;
+
+    /// Identifier of the exact model and parameters; changes whenever the
+    /// produced vectors would change
+    fn version(&self) -> &str;
+
+    /// Computes the embedding of a single text
+    fn embed(&self, text: &str) -> Result<Vec<f32>>;
+
+    /// Computes the embeddings of several texts, in order
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        texts.iter().map(|text| self.embed(text)).collect()
+    }
 }
 
-/// Computes an embedding (float vector) for a given input text using N-gram
-/// hashing.
-pub fn compute_embedding(text: &str) -> Result<Vec<f32>> {
-    // Initialize an embedding vector with zeros.
-    // The size of the vector is determined by the vocabulary size.
-    let mut embedding = vec![0f32; VOCAB_SIZE];
-    let bytes = text.as_bytes();
-    if bytes.len() < N_GRAM {
-        return Ok(embedding);
+/// Builds the embedder selected by `EMBEDDER` in the configuration.
+/// The TF-IDF embedder is loaded from `TFIDF_MODEL_PATH`, so it must have
+/// been fitted with `backend embeddings refit` beforehand; the Candle
+/// embedder is loaded from `CANDLE_MODEL_DIR`.
+pub fn from_config(config: &Config) -> Result<Arc<dyn Embedder>> {
+    if config.embedder == TfidfEmbedder::NAME {
+        return Ok(Arc::new(TfidfEmbedder::load(Path::new(
+            &config.tfidf_model_path,
+        ))?));
     }
-    for ngram in bytes.windows(N_GRAM) {
-        let idx = ngram_hash(ngram);
-        embedding[idx] += 1.0;
+    #[cfg(feature = "candle")]
+    if config.embedder == CandleEmbedder::NAME {
+        return Ok(Arc::new(CandleEmbedder::load(Path::new(
+            &config.candle_model_dir,
+        ))?));
     }
-    // Compute the L2 norm (Euclidean norm) of the embedding vector.
-    // This is done by summing the squares of each element, then taking the
-    // square root.
-    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
-    if norm > 0.0 {
-        for v in &mut embedding {
-            *v /= norm;
-        }
+    #[cfg(not(feature = "candle"))]
+    if config.embedder == "candle" {
+        return Err(anyhow::anyhow!(
+            "EMBEDDER=candle requires building with `--features candle`"
+        ));
     }
-    Ok(embedding)
+
+    let options = HashingOptions {
+        dimension: config.embedding_dimension,
+        char_ngrams: config.embedding_char_ngrams,
+        word_ngrams: config.embedding_word_ngrams,
+        signed: config.embedding_signed_hashing,
+        sublinear_tf: config.embedding_sublinear_tf,
+        normalization: NormalizationOptions {
+            strip_accents: config.embedding_strip_accents,
+            ..NormalizationOptions::default()
+        },
+    };
+    from_name(&config.embedder, options)
+}
+
+/// Options for fitting a TF-IDF embedder, taken from the configuration
+pub fn tfidf_options(config: &Config) -> TfidfOptions {
+    TfidfOptions {
+        dimension: config.embedding_dimension,
+        min_document_frequency: config.tfidf_min_df,
+        max_document_ratio: config.tfidf_max_df,
+        sublinear_tf: config.embedding_sublinear_tf,
+        normalization: NormalizationOptions {
+            strip_accents: config.embedding_strip_accents,
+            ..NormalizationOptions::default()
+        },
+    }
+}
+
+/// Builds an embedder that needs no fitting by its [`Embedder::name`]
+pub fn from_name(name: &str, options: HashingOptions) -> Result<Arc<dyn Embedder>> {
+    match name {
+        HashingEmbedder::NAME => Ok(Arc::new(HashingEmbedder::with_options(options)?)),
+        other => Err(anyhow::anyhow!("Unknown embedder: {}", other)),
+    }
+}
+
+/// Computes an embedding (float vector) for a given input text using the
+/// default N-gram hashing embedder.
+pub fn compute_embedding(text: &str) -> Result<Vec<f32>> {
+    HashingEmbedder::new().embed(text)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_ngram_hash_consistency() {
-        let ngram = b"abc";
-        let idx1 = ngram_hash(ngram);
-        let idx2 = ngram_hash(ngram);
-        assert_eq!(idx1, idx2);
-        assert!(idx1 < VOCAB_SIZE);
-    }
-
     #[test]
     fn test_compute_embedding_empty() {
         let emb = compute_embedding("").unwrap();
-        assert_eq!(emb.len(), VOCAB_SIZE);
+        assert_eq!(emb.len(), HashingEmbedder::new().dimension());
         assert!(emb.iter().all(|&x| x == 0.0));
     }
 
     #[test]
     fn test_compute_embedding_short_text() {
         let emb = compute_embedding("ab").unwrap();
-        assert_eq!(emb.len(), VOCAB_SIZE);
+        assert_eq!(emb.len(), HashingEmbedder::new().dimension());
         assert!(emb.iter().all(|&x| x == 0.0));
     }
 
     #[test]
     fn test_compute_embedding_basic() {
         let emb = compute_embedding("abcd").unwrap();
-        assert_eq!(emb.len(), VOCAB_SIZE);
+        assert_eq!(emb.len(), HashingEmbedder::new().dimension());
         let nonzero: Vec<_> = emb.iter().filter(|&&x| x > 0.0).collect();
-        assert!(nonzero.len() > 0);
+        assert!(!nonzero.is_empty());
         let norm = emb.iter().map(|x| x * x).sum::<f32>().sqrt();
         assert!((norm - 1.0).abs() < 1e-5);
     }
+
+    #[test]
+    fn test_from_name_selects_embedder() {
+        let embedder = from_name("hashing", HashingOptions::default()).unwrap();
+        assert_eq!(embedder.name(), "hashing");
+        assert!(from_name("does-not-exist", HashingOptions::default()).is_err());
+    }
 }
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// Steps applied to text before it is split into n-grams
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormalizationOptions {
+    /// Apply Unicode NFKC, so compatibility forms (ligatures, full-width
+    /// letters, precomposed vs. decomposed accents) share one representation
+    pub nfkc: bool,
+    /// Lowercase the text, so "Casmurro" and "casmurro" match
+    pub case_fold: bool,
+    /// Remove diacritics, so "ciúme" and "ciume" match
+    pub strip_accents: bool,
+    /// Replace every run of whitespace and punctuation with a single space
+    pub collapse_punctuation: bool,
+}
+
+impl Default for NormalizationOptions {
+    fn default() -> Self {
+        NormalizationOptions {
+            nfkc: true,
+            case_fold: true,
+            strip_accents: true,
+            collapse_punctuation: true,
+        }
+    }
+}
+
+impl NormalizationOptions {
+    /// Leaves the text untouched
+    pub fn none() -> Self {
+        NormalizationOptions {
+            nfkc: false,
+            case_fold: false,
+            strip_accents: false,
+            collapse_punctuation: false,
+        }
+    }
+
+    /// Compact identifier of the enabled steps, used in embedder versions
+    pub fn tag(&self) -> String {
+        let steps = [
+            (self.nfkc, "nfkc"),
+            (self.case_fold, "fold"),
+            (self.strip_accents, "noacc"),
+            (self.collapse_punctuation, "punct"),
+        ];
+        let enabled: Vec<&str> = steps
+            .iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, name)| *name)
+            .collect();
+        if enabled.is_empty() {
+            "raw".to_string()
+        } else {
+            enabled.join("+")
+        }
+    }
+}
+
+/// Normalizes `text` according to `options`
+pub fn normalize(text: &str, options: &NormalizationOptions) -> String {
+    let mut text: String = if options.nfkc {
+        text.nfkc().collect()
+    } else {
+        text.to_string()
+    };
+
+    if options.case_fold {
+        text = text.to_lowercase();
+    }
+
+    if options.strip_accents {
+        text = text
+            .nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .nfc()
+            .collect();
+    }
+
+    if options.collapse_punctuation {
+        text = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_accents_and_case() {
+        let options = NormalizationOptions::default();
+        assert_eq!(normalize("Ciúme", &options), "ciume");
+        assert_eq!(normalize("CASMURRO", &options), "casmurro");
+        assert_eq!(normalize("Ação e reação", &options), "acao e reacao");
+    }
+
+    #[test]
+    fn test_normalize_composed_and_decomposed_match() {
+        let options = NormalizationOptions {
+            strip_accents: false,
+            ..NormalizationOptions::default()
+        };
+        // "é" precomposed vs. "e" + combining acute accent
+        assert_eq!(
+            normalize("caf\u{e9}", &options),
+            normalize("cafe\u{301}", &options)
+        );
+    }
+
+    #[test]
+    fn test_normalize_collapses_punctuation() {
+        let options = NormalizationOptions::default();
+        assert_eq!(
+            normalize("  Dom   Casmurro!!! -- (1899)  ", &options),
+            "dom casmurro 1899"
+        );
+    }
+
+    #[test]
+    fn test_normalize_compatibility_forms() {
+        let options = NormalizationOptions::default();
+        // Ligature "ﬁ" and full-width letters
+        assert_eq!(normalize("ﬁm", &options), "fim");
+        assert_eq!(normalize("ＡＢＣ", &options), "abc");
+    }
+
+    #[test]
+    fn test_normalize_none_is_identity() {
+        let text = "  Ciúme, CASMURRO!  ";
+        assert_eq!(normalize(text, &NormalizationOptions::none()), text);
+    }
+
+    #[test]
+    fn test_tag() {
+        assert_eq!(
+            NormalizationOptions::default().tag(),
+            "nfkc+fold+noacc+punct"
+        );
+        assert_eq!(NormalizationOptions::none().tag(), "raw");
+    }
+}
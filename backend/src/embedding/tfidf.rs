@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Embedder;
+use super::hashing::fnv1a_64;
+use super::normalize::{NormalizationOptions, normalize};
+
+/// Parameters used when fitting a [`TfidfEmbedder`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TfidfOptions {
+    /// Length of every vector; the vocabulary keeps at most this many terms
+    pub dimension: usize,
+    /// Terms appearing in fewer documents than this are left out
+    pub min_document_frequency: usize,
+    /// Terms appearing in more than this fraction of the documents are left
+    /// out
+    #[serde(default = "default_max_document_ratio")]
+    pub max_document_ratio: f32,
+    /// Replace raw counts `tf` with `1 + ln(tf)`
+    pub sublinear_tf: bool,
+    /// Text normalization applied before splitting into terms
+    pub normalization: NormalizationOptions,
+}
+
+impl Default for TfidfOptions {
+    fn default() -> Self {
+        TfidfOptions {
+            dimension: 512,
+            min_document_frequency: 1,
+            max_document_ratio: default_max_document_ratio(),
+            sublinear_tf: false,
+            normalization: NormalizationOptions::default(),
+        }
+    }
+}
+
+/// Models saved before `max_document_ratio` existed kept every term
+fn default_max_document_ratio() -> f32 {
+    1.0
+}
+
+/// Vocabulary and IDF table fitted over a corpus; this is what gets persisted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TfidfModel {
+    options: TfidfOptions,
+    document_count: usize,
+    /// Vocabulary terms; a term's position is its vector index
+    vocabulary: Vec<String>,
+    /// Inverse document frequency of each vocabulary term
+    idf: Vec<f32>,
+}
+
+/// Embeds text as TF-IDF weighted word counts over a vocabulary fitted on
+/// the catalog, so common function words ("de", "a", "que") weigh little
+/// and distinctive terms weigh a lot.
+#[derive(Debug, Clone)]
+pub struct TfidfEmbedder {
+    model: TfidfModel,
+    index: HashMap<String, usize>,
+    version: String,
+}
+
+impl TfidfEmbedder {
+    pub const NAME: &'static str = "tfidf";
+
+    /// Fits a vocabulary and IDF table over `documents`
+    pub fn fit(documents: &[&str], options: TfidfOptions) -> Result<Self> {
+        if options.dimension == 0 {
+            return Err(anyhow::anyhow!("Embedding dimension must be at least 1"));
+        }
+        if !(options.max_document_ratio > 0.0 && options.max_document_ratio <= 1.0) {
+            return Err(anyhow::anyhow!(
+                "Maximum document ratio must be in (0, 1], got {}",
+                options.max_document_ratio
+            ));
+        }
+
+        // Document frequency of every term; BTreeMap keeps ties deterministic
+        let mut document_frequency: BTreeMap<String, usize> = BTreeMap::new();
+        for document in documents {
+            let terms: HashSet<String> = tokenize(document, &options.normalization)
+                .into_iter()
+                .collect();
+            for term in terms {
+                *document_frequency.entry(term).or_insert(0) += 1;
+            }
+        }
+
+        // Within the frequency bounds, keep the most widespread terms, as
+        // the vocabulary cannot exceed the dimension: terms found in a single
+        // abstract (often typos) rarely occur in other texts or in queries.
+        // Function words are left to `max_document_ratio`.
+        let document_count = documents.len();
+        let max_df = (options.max_document_ratio * document_count as f32).floor() as usize;
+        let mut terms: Vec<(String, usize)> = document_frequency
+            .into_iter()
+            .filter(|(_, df)| *df >= options.min_document_frequency.max(1) && *df <= max_df)
+            .collect();
+        terms.sort_by(|(a, a_df), (b, b_df)| b_df.cmp(a_df).then_with(|| a.cmp(b)));
+        terms.truncate(options.dimension);
+
+        let (vocabulary, idf) = terms
+            .into_iter()
+            .map(|(term, df)| {
+                // Smoothed IDF, always positive
+                let idf = ((1.0 + document_count as f32) / (1.0 + df as f32)).ln() + 1.0;
+                (term, idf)
+            })
+            .unzip();
+
+        Ok(Self::from_model(TfidfModel {
+            options,
+            document_count,
+            vocabulary,
+            idf,
+        }))
+    }
+
+    /// Loads a model previously written by [`TfidfEmbedder::save`]
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to read TF-IDF model {}: {}. Run `backend embeddings refit` first",
+                path.display(),
+                e
+            )
+        })?;
+        let model: TfidfModel = serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid TF-IDF model {}: {}", path.display(), e))?;
+        if model.vocabulary.len() != model.idf.len()
+            || model.vocabulary.len() > model.options.dimension
+        {
+            return Err(anyhow::anyhow!(
+                "Invalid TF-IDF model {}: inconsistent vocabulary",
+                path.display()
+            ));
+        }
+
+        Ok(Self::from_model(model))
+    }
+
+    /// Writes the vocabulary and IDF table to `path` as JSON
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string(&self.model)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize TF-IDF model: {}", e))?;
+        // Write then rename, so a crash never leaves a truncated model behind
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, contents)
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .map_err(|e| anyhow::anyhow!("Failed to write TF-IDF model {}: {}", path.display(), e))
+    }
+
+    /// Number of terms in the fitted vocabulary
+    pub fn vocabulary_size(&self) -> usize {
+        self.model.vocabulary.len()
+    }
+
+    /// Number of documents the model was fitted on
+    pub fn document_count(&self) -> usize {
+        self.model.document_count
+    }
+
+    fn from_model(model: TfidfModel) -> Self {
+        let index = model
+            .vocabulary
+            .iter()
+            .enumerate()
+            .map(|(i, term)| (term.clone(), i))
+            .collect();
+
+        // Every refit changes the vectors, so the version fingerprints the
+        // fitted vocabulary and IDF table along with the options
+        let mut fingerprint = Vec::new();
+        for (term, idf) in model.vocabulary.iter().zip(&model.idf) {
+            fingerprint.extend_from_slice(term.as_bytes());
+            fingerprint.push(0);
+            fingerprint.extend_from_slice(&idf.to_le_bytes());
+        }
+        let version = format!(
+            "tfidf-{}{}-{}-{:016x}",
+            model.options.dimension,
+            if model.options.sublinear_tf {
+                "-logtf"
+            } else {
+                ""
+            },
+            model.options.normalization.tag(),
+            fnv1a_64(&fingerprint)
+        );
+
+        TfidfEmbedder {
+            model,
+            index,
+            version,
+        }
+    }
+}
+
+/// Splits normalized text into word terms
+fn tokenize(text: &str, normalization: &NormalizationOptions) -> Vec<String> {
+    normalize(text, normalization)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl Embedder for TfidfEmbedder {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn dimension(&self) -> usize {
+        self.model.options.dimension
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let mut embedding = vec![0f32; self.model.options.dimension];
+        for term in tokenize(text, &self.model.options.normalization) {
+            if let Some(&idx) = self.index.get(&term) {
+                embedding[idx] += 1.0;
+            }
+        }
+
+        for (v, idf) in embedding.iter_mut().zip(&self.model.idf) {
+            if *v > 0.0 {
+                let tf = if self.model.options.sublinear_tf {
+                    1.0 + v.ln()
+                } else {
+                    *v
+                };
+                *v = tf * idf;
+            }
+        }
+
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            for v in &mut embedding {
+                *v /= norm;
+            }
+        }
+        Ok(embedding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: &[&str] = &[
+        "A história de um homem e de seu ciúme",
+        "A história de uma cidade e de seu povo",
+        "O mar e a memória de um pescador",
+    ];
+
+    fn fitted() -> TfidfEmbedder {
+        TfidfEmbedder::fit(CORPUS, TfidfOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_fit_builds_vocabulary() {
+        let embedder = fitted();
+        assert_eq!(embedder.document_count(), 3);
+        assert!(embedder.vocabulary_size() > 0);
+        assert!(embedder.vocabulary_size() <= embedder.dimension());
+    }
+
+    #[test]
+    fn test_common_terms_weigh_less() {
+        let embedder = fitted();
+        let idf = |term: &str| embedder.model.idf[embedder.index[term]];
+        // "de" appears in every document, "ciume" in only one
+        assert!(idf("de") < idf("ciume"));
+    }
+
+    #[test]
+    fn test_embedding_is_normalized() {
+        let embedding = fitted().embed("O ciúme de um homem").unwrap();
+        assert_eq!(embedding.len(), 512);
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_unknown_terms_embed_to_zero() {
+        let embedding = fitted().embed("xyzzy").unwrap();
+        assert!(embedding.iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn test_vocabulary_is_capped_by_dimension() {
+        let options = TfidfOptions {
+            dimension: 4,
+            ..TfidfOptions::default()
+        };
+        let embedder = TfidfEmbedder::fit(CORPUS, options).unwrap();
+        assert_eq!(embedder.vocabulary_size(), 4);
+        assert_eq!(embedder.embed("história").unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_truncation_keeps_most_frequent_terms() {
+        let options = TfidfOptions {
+            dimension: 3,
+            max_document_ratio: 0.7,
+            ..TfidfOptions::default()
+        };
+        let embedder = TfidfEmbedder::fit(CORPUS, options).unwrap();
+        // "de" is in every document and above the ratio, "historia", "seu"
+        // and "um" in two, "ciume" in one
+        assert_eq!(embedder.model.vocabulary, vec!["historia", "seu", "um"]);
+        assert!(!embedder.index.contains_key("de"));
+        assert!(!embedder.index.contains_key("ciume"));
+    }
+
+    #[test]
+    fn test_max_document_ratio() {
+        let options = TfidfOptions {
+            max_document_ratio: 0.7,
+            ..TfidfOptions::default()
+        };
+        let embedder = TfidfEmbedder::fit(CORPUS, options).unwrap();
+        assert!(embedder.index.contains_key("historia"));
+        assert!(!embedder.index.contains_key("de"));
+
+        let options = TfidfOptions {
+            max_document_ratio: 0.0,
+            ..TfidfOptions::default()
+        };
+        assert!(TfidfEmbedder::fit(CORPUS, options).is_err());
+    }
+
+    #[test]
+    fn test_min_document_frequency() {
+        let options = TfidfOptions {
+            min_document_frequency: 2,
+            ..TfidfOptions::default()
+        };
+        let embedder = TfidfEmbedder::fit(CORPUS, options).unwrap();
+        assert!(embedder.index.contains_key("historia"));
+        assert!(!embedder.index.contains_key("pescador"));
+    }
+
+    #[test]
+    fn test_refit_changes_version() {
+        let other = TfidfEmbedder::fit(&CORPUS[..2], TfidfOptions::default()).unwrap();
+        assert_ne!(fitted().version(), other.version());
+        assert_eq!(fitted().version(), fitted().version());
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let embedder = fitted();
+        let path =
+            std::env::temp_dir().join(format!("icarusp-tfidf-test-{}.json", std::process::id()));
+
+        embedder.save(&path).unwrap();
+        let loaded = TfidfEmbedder::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.version(), embedder.version());
+        assert_eq!(
+            loaded.embed("O ciúme de um homem").unwrap(),
+            embedder.embed("O ciúme de um homem").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_load_missing_model() {
+        assert!(TfidfEmbedder::load(Path::new("/nonexistent/tfidf.json")).is_err());
+    }
+}
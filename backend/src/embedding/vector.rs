@@ -0,0 +1,219 @@
+//! Vector math over embeddings held in memory, for reranking and analytics
+//! that happen after vectors come back from the database
+
+use anyhow::Result;
+use pgvector::Vector;
+
+/// Anything that can be viewed as a dense vector of floats
+pub trait DenseVector {
+    fn values(&self) -> &[f32];
+}
+
+impl DenseVector for [f32] {
+    fn values(&self) -> &[f32] {
+        self
+    }
+}
+
+impl DenseVector for Vec<f32> {
+    fn values(&self) -> &[f32] {
+        self
+    }
+}
+
+impl DenseVector for Vector {
+    fn values(&self) -> &[f32] {
+        self.as_slice()
+    }
+}
+
+impl<T: DenseVector + ?Sized> DenseVector for &T {
+    fn values(&self) -> &[f32] {
+        (**self).values()
+    }
+}
+
+fn check_dimensions(a: &[f32], b: &[f32]) -> Result<()> {
+    if a.len() != b.len() {
+        return Err(anyhow::anyhow!(
+            "Vector dimensions differ: {} vs {}",
+            a.len(),
+            b.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Dot product of two vectors of the same dimension
+pub fn dot<A: DenseVector + ?Sized, B: DenseVector + ?Sized>(a: &A, b: &B) -> Result<f32> {
+    let (a, b) = (a.values(), b.values());
+    check_dimensions(a, b)?;
+    Ok(a.iter().zip(b).map(|(x, y)| x * y).sum())
+}
+
+/// Euclidean (L2) norm of a vector
+pub fn norm<V: DenseVector + ?Sized>(v: &V) -> f32 {
+    v.values().iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// Cosine similarity of two vectors, in `[-1, 1]`. A zero vector has no
+/// direction, so its similarity with anything is 0.
+pub fn cosine_similarity<A: DenseVector + ?Sized, B: DenseVector + ?Sized>(
+    a: &A,
+    b: &B,
+) -> Result<f32> {
+    let product = dot(a, b)?;
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        return Ok(0.0);
+    }
+    // Rounding can push the ratio slightly outside [-1, 1]
+    Ok((product / norms).clamp(-1.0, 1.0))
+}
+
+/// Cosine similarity of every distinct pair of `embeddings`, as
+/// `(similarity, i, j)` with `i < j`, ordered by `i` then `j`
+pub fn compute_cosine_similarity<V: DenseVector>(
+    embeddings: &[V],
+) -> Result<Vec<(f32, usize, usize)>> {
+    let mut pairs = Vec::with_capacity(embeddings.len() * embeddings.len().saturating_sub(1) / 2);
+    for i in 0..embeddings.len() {
+        for j in (i + 1)..embeddings.len() {
+            pairs.push((cosine_similarity(&embeddings[i], &embeddings[j])?, i, j));
+        }
+    }
+    Ok(pairs)
+}
+
+/// Square matrix whose entry `[i][j]` is the cosine similarity of
+/// `embeddings[i]` and `embeddings[j]`
+pub fn similarity_matrix<V: DenseVector>(embeddings: &[V]) -> Result<Vec<Vec<f32>>> {
+    let n = embeddings.len();
+    let mut matrix = vec![vec![0f32; n]; n];
+    for i in 0..n {
+        matrix[i][i] = if norm(&embeddings[i]) > 0.0 { 1.0 } else { 0.0 };
+        for j in (i + 1)..n {
+            let similarity = cosine_similarity(&embeddings[i], &embeddings[j])?;
+            matrix[i][j] = similarity;
+            matrix[j][i] = similarity;
+        }
+    }
+    Ok(matrix)
+}
+
+/// Element-wise mean of `embeddings`
+pub fn centroid<V: DenseVector>(embeddings: &[V]) -> Result<Vec<f32>> {
+    let first = embeddings
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("Cannot compute the centroid of no vectors"))?;
+
+    let mut sum = vec![0f32; first.values().len()];
+    for embedding in embeddings {
+        let values = embedding.values();
+        check_dimensions(&sum, values)?;
+        for (total, v) in sum.iter_mut().zip(values) {
+            *total += v;
+        }
+    }
+
+    let count = embeddings.len() as f32;
+    Ok(sum.into_iter().map(|total| total / count).collect())
+}
+
+/// The `k` candidates most similar to `query`, as `(similarity, index)`
+/// ordered from most to least similar; ties keep the lower index first.
+/// Candidates holding NaN have no similarity and are left out.
+pub fn top_k<Q: DenseVector + ?Sized, V: DenseVector>(
+    query: &Q,
+    candidates: &[V],
+    k: usize,
+) -> Result<Vec<(f32, usize)>> {
+    let mut scored = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, candidate)| Ok((cosine_similarity(query, candidate)?, i)))
+        .collect::<Result<Vec<_>>>()?;
+    scored.retain(|(similarity, _)| !similarity.is_nan());
+
+    scored.sort_by(|(a, i), (b, j)| b.total_cmp(a).then_with(|| i.cmp(j)));
+    scored.truncate(k);
+    Ok(scored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot_and_norm() {
+        assert_eq!(
+            dot(&vec![1.0, 2.0, 3.0], &vec![4.0, 5.0, 6.0]).unwrap(),
+            32.0
+        );
+        assert_eq!(norm(&vec![3.0, 4.0]), 5.0);
+        assert!(dot(&vec![1.0], &vec![1.0, 2.0]).is_err());
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        let a = vec![1.0, 0.0];
+        assert!((cosine_similarity(&a, &vec![2.0, 0.0]).unwrap() - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&a, &vec![0.0, 1.0]).unwrap().abs() < 1e-6);
+        assert!((cosine_similarity(&a, &vec![-1.0, 0.0]).unwrap() + 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&a, &vec![0.0, 0.0]).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_cosine_similarity_with_pgvector() {
+        let a = Vector::from(vec![1.0, 1.0]);
+        let b = vec![1.0, 1.0];
+        assert!((cosine_similarity(&a, &b).unwrap() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_compute_cosine_similarity_pairs() {
+        let embeddings = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0]];
+        let pairs = compute_cosine_similarity(&embeddings).unwrap();
+        let indices: Vec<(usize, usize)> = pairs.iter().map(|&(_, i, j)| (i, j)).collect();
+        assert_eq!(indices, vec![(0, 1), (0, 2), (1, 2)]);
+        assert!((pairs[1].0 - 1.0).abs() < 1e-6);
+        assert!(
+            compute_cosine_similarity::<Vec<f32>>(&[])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_similarity_matrix() {
+        let embeddings = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        let matrix = similarity_matrix(&embeddings).unwrap();
+        assert_eq!(matrix, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    }
+
+    #[test]
+    fn test_centroid() {
+        let embeddings = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        assert_eq!(centroid(&embeddings).unwrap(), vec![2.0, 3.0]);
+        assert!(centroid::<Vec<f32>>(&[]).is_err());
+        assert!(centroid(&[vec![1.0], vec![1.0, 2.0]]).is_err());
+    }
+
+    #[test]
+    fn test_top_k() {
+        let candidates = vec![
+            vec![0.0, 1.0],
+            vec![1.0, 0.1],
+            vec![1.0, 0.0],
+            vec![1.0, 0.0],
+        ];
+        let top = top_k(&vec![1.0, 0.0], &candidates, 3).unwrap();
+        let indices: Vec<usize> = top.iter().map(|&(_, i)| i).collect();
+        assert_eq!(indices, vec![2, 3, 1]);
+        assert!(top_k(&vec![1.0, 0.0], &candidates, 0).unwrap().is_empty());
+
+        let candidates = vec![vec![f32::NAN, 1.0], vec![0.0, 1.0], vec![1.0, 0.0]];
+        let top = top_k(&vec![1.0, 0.0], &candidates, 3).unwrap();
+        assert_eq!(top, vec![(1.0, 2), (0.0, 1)]);
+    }
+}
@@ -0,0 +1,300 @@
+//! Errors of the data layer, classified by what the client can do about
+//! them. Each kind maps to an HTTP status and a machine-readable code; the
+//! message of an internal error stays in the logs, so raw Postgres messages
+//! and connection details never reach clients.
+
+use std::fmt::Display;
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use diesel::result::DatabaseErrorKind;
+use serde::Serialize;
+
+use crate::response::ApiResponse;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The record asked for does not exist
+    NotFound(String),
+    /// The change clashes with existing data, such as a duplicate title
+    Conflict(String),
+    /// The request cannot be read, such as malformed JSON or a cursor this
+    /// server did not issue, or it gives nothing to act on
+    BadRequest(String),
+    /// The request breaks a rule of the data
+    Validation(String),
+    /// Some fields of the request break a rule, each described separately
+    InvalidFields(Vec<FieldError>),
+    /// The database cannot take the request right now; retrying may succeed
+    Unavailable(String),
+    /// Anything else; only logged, never shown to clients
+    Internal(anyhow::Error),
+}
+
+impl Error {
+    /// `entity` with ID `id` does not exist
+    pub fn not_found(entity: &str, id: i32) -> Self {
+        Error::NotFound(format!("{} with ID {} not found", entity, id))
+    }
+
+    /// Machine-readable kind of the error, sent along with its message
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::NotFound(_) => "not_found",
+            Error::Conflict(_) => "conflict",
+            Error::BadRequest(_) => "bad_request",
+            Error::Validation(_) | Error::InvalidFields(_) => "validation_failed",
+            Error::Unavailable(_) => "unavailable",
+            Error::Internal(_) => "internal",
+        }
+    }
+
+    /// Message safe to show to clients
+    pub fn client_message(&self) -> String {
+        match self {
+            Error::NotFound(message)
+            | Error::Conflict(message)
+            | Error::BadRequest(message)
+            | Error::Validation(message)
+            | Error::Unavailable(message) => message.clone(),
+            Error::InvalidFields(errors) => {
+                let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+                format!("Invalid fields: {}", fields.join(", "))
+            }
+            Error::Internal(_) => "Internal server error".to_string(),
+        }
+    }
+
+    /// Describes what was being done when an internal error happened; other
+    /// kinds already carry a message meant for the client
+    pub fn context(self, context: impl Display + Send + Sync + 'static) -> Self {
+        match self {
+            Error::Internal(e) => Error::Internal(e.context(context)),
+            e => e,
+        }
+    }
+}
+
+/// Why one field of a request is invalid
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Internal(e) => write!(f, "{:#}", e),
+            e => write!(f, "{}", e.client_message()),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Postgres' own message for a query cancelled by `statement_timeout`
+const STATEMENT_TIMEOUT_MESSAGE: &str = "canceling statement due to statement timeout";
+
+impl From<diesel::result::Error> for Error {
+    fn from(error: diesel::result::Error) -> Self {
+        use diesel::result::Error::{DatabaseError, NotFound};
+
+        match &error {
+            NotFound => Error::NotFound("Record not found".to_string()),
+            DatabaseError(DatabaseErrorKind::UniqueViolation, info) => Error::Conflict(
+                match info.constraint_name() {
+                    Some("books_live_title_key") => "A book with this title already exists",
+                    Some("authors_live_name_key") => "An author with this name already exists",
+                    Some("books_authors_pkey") => "The book is already linked to this author",
+                    _ => "A record with the same values already exists",
+                }
+                .to_string(),
+            ),
+            DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => Error::Conflict(
+                "The change refers to a record that does not exist or is still in use".to_string(),
+            ),
+            DatabaseError(DatabaseErrorKind::NotNullViolation, _)
+            | DatabaseError(DatabaseErrorKind::CheckViolation, _) => {
+                Error::Validation("The record breaks a rule of the database".to_string())
+            }
+            DatabaseError(DatabaseErrorKind::SerializationFailure, _)
+            | DatabaseError(DatabaseErrorKind::ClosedConnection, _) => {
+                log::warn!("Database unavailable: {}", error);
+                Error::Unavailable("Database is busy, please retry shortly".to_string())
+            }
+            DatabaseError(_, info) if info.message() == STATEMENT_TIMEOUT_MESSAGE => {
+                log::warn!("Database query timed out");
+                Error::Unavailable("The query took too long, please retry shortly".to_string())
+            }
+            _ => Error::Internal(error.into()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(error: anyhow::Error) -> Self {
+        // Keep the kind of an error that passed through anyhow on the way up
+        error.downcast::<Error>().unwrap_or_else(Error::Internal)
+    }
+}
+
+/// Adds context to the internal errors of a `Result`, like
+/// [`anyhow::Context`] does
+pub trait ResultExt<T> {
+    fn context(self, context: impl Display + Send + Sync + 'static) -> Result<T>;
+
+    fn with_context<C, F>(self, context: F) -> Result<T>
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for std::result::Result<T, E> {
+    fn context(self, context: impl Display + Send + Sync + 'static) -> Result<T> {
+        self.map_err(|e| e.into().context(context))
+    }
+
+    fn with_context<C, F>(self, context: F) -> Result<T>
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|e| e.into().context(context()))
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::Conflict(_) => StatusCode::CONFLICT,
+            Error::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Error::Validation(_) | Error::InvalidFields(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            Error::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if let Error::Internal(e) = self {
+            log::error!("{:#}", e);
+        }
+        let mut body = ApiResponse::<()>::error(self.code(), self.client_message());
+        if let Error::InvalidFields(errors) = self {
+            body.errors = Some(errors.clone());
+        }
+        HttpResponse::build(self.status_code()).json(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::result::DatabaseErrorInformation;
+
+    #[derive(Debug)]
+    struct Info {
+        message: &'static str,
+        constraint: Option<&'static str>,
+    }
+
+    impl DatabaseErrorInformation for Info {
+        fn message(&self) -> &str {
+            self.message
+        }
+        fn details(&self) -> Option<&str> {
+            None
+        }
+        fn hint(&self) -> Option<&str> {
+            None
+        }
+        fn table_name(&self) -> Option<&str> {
+            None
+        }
+        fn column_name(&self) -> Option<&str> {
+            None
+        }
+        fn constraint_name(&self) -> Option<&str> {
+            self.constraint
+        }
+        fn statement_position(&self) -> Option<i32> {
+            None
+        }
+    }
+
+    fn database_error(
+        kind: DatabaseErrorKind,
+        message: &'static str,
+        constraint: Option<&'static str>,
+    ) -> Error {
+        Error::from(diesel::result::Error::DatabaseError(
+            kind,
+            Box::new(Info {
+                message,
+                constraint,
+            }),
+        ))
+    }
+
+    #[test]
+    fn test_classifies_database_errors() {
+        let duplicate = database_error(
+            DatabaseErrorKind::UniqueViolation,
+            "duplicate key value violates unique constraint \"books_live_title_key\"",
+            Some("books_live_title_key"),
+        );
+        assert!(matches!(duplicate, Error::Conflict(_)));
+        assert_eq!(duplicate.status_code(), StatusCode::CONFLICT);
+        assert_eq!(
+            duplicate.client_message(),
+            "A book with this title already exists"
+        );
+
+        let timeout = database_error(DatabaseErrorKind::Unknown, STATEMENT_TIMEOUT_MESSAGE, None);
+        assert_eq!(timeout.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let missing = Error::from(diesel::result::Error::NotFound);
+        assert_eq!(missing.code(), "not_found");
+    }
+
+    #[test]
+    fn test_bad_request_is_not_a_validation_failure() {
+        let malformed = Error::BadRequest("Malformed cursor".to_string());
+        assert_eq!(malformed.code(), "bad_request");
+        assert_eq!(malformed.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(malformed.client_message(), "Malformed cursor");
+
+        let invalid = Error::Validation("limit must be between 1 and 100".to_string());
+        assert_eq!(invalid.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn test_internal_errors_are_hidden() {
+        let error = Error::from(anyhow::anyhow!(
+            "could not connect to postgres://user:secret@db/icarusp"
+        ))
+        .context("Failed to load books");
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.client_message(), "Internal server error");
+        assert!(error.to_string().starts_with("Failed to load books: "));
+    }
+
+    #[test]
+    fn test_kind_survives_anyhow() {
+        let error = Error::from(anyhow::Error::from(Error::not_found("Book", 7)));
+        assert!(matches!(error, Error::NotFound(_)));
+        assert_eq!(error.client_message(), "Book with ID 7 not found");
+    }
+}
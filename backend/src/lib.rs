@@ -3,14 +3,20 @@
 //! This library provides the core functionality for the Icarusp backend,
 //! including database operations, embedding generation, and data models.
 
+pub mod cli;
 pub mod database;
 pub mod embedding;
+pub mod error;
 pub mod models;
+pub mod params;
+pub mod response;
 pub mod schema;
+pub mod validation;
 
 use anyhow::Result;
 use database::initialization::establish_connection;
 use diesel::PgConnection;
+use embedding::{NgramRange, parse_ngram_range};
 
 /// Initialize the application and test database connection
 pub fn initialize() -> Result<()> {
@@ -32,6 +38,38 @@ pub struct Config {
     pub database_url: String,
     pub server_host: String,
     pub server_port: u16,
+    /// Maximum number of pooled database connections
+    pub db_pool_max_size: u32,
+    /// Seconds to wait for a pooled connection before giving up
+    pub db_pool_timeout_secs: u64,
+    /// Per-connection `statement_timeout` in milliseconds (0 disables it)
+    pub db_statement_timeout_ms: u64,
+    /// Maximum number of embedding computations running at once
+    pub embedding_concurrency: usize,
+    /// Name of the embedder used for books and queries (see `embedding::from_name`)
+    pub embedder: String,
+    /// Whether text is stripped of accents before embedding
+    pub embedding_strip_accents: bool,
+    /// Length of the vectors produced by the hashing embedder; must match
+    /// the `VECTOR(n)` type of `books.embedding`
+    pub embedding_dimension: usize,
+    /// Character n-gram lengths hashed by the hashing embedder
+    pub embedding_char_ngrams: Option<NgramRange>,
+    /// Word n-gram lengths hashed by the hashing embedder
+    pub embedding_word_ngrams: Option<NgramRange>,
+    /// Whether the hashing embedder uses signed hashing
+    pub embedding_signed_hashing: bool,
+    /// Whether the hashing embedder uses sublinear (logarithmic) term frequencies
+    pub embedding_sublinear_tf: bool,
+    /// File holding the fitted vocabulary and IDF table of the TF-IDF embedder
+    pub tfidf_model_path: String,
+    /// Minimum number of abstracts a term must appear in to enter the TF-IDF vocabulary
+    pub tfidf_min_df: usize,
+    /// Largest fraction of abstracts a term may appear in to enter the TF-IDF vocabulary
+    pub tfidf_max_df: f32,
+    /// Directory holding the sentence-transformer used by the Candle embedder
+    /// (`config.json`, `tokenizer.json` and `model.safetensors`)
+    pub candle_model_dir: String,
 }
 
 impl Config {
@@ -48,10 +86,103 @@ impl Config {
             .parse::<u16>()
             .map_err(|e| anyhow::anyhow!("Invalid SERVER_PORT: {}", e))?;
 
+        let db_pool_max_size = std::env::var("DB_POOL_MAX_SIZE")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u32>()
+            .map_err(|e| anyhow::anyhow!("Invalid DB_POOL_MAX_SIZE: {}", e))?;
+        if db_pool_max_size == 0 {
+            return Err(anyhow::anyhow!("DB_POOL_MAX_SIZE must be at least 1"));
+        }
+
+        let db_pool_timeout_secs = std::env::var("DB_POOL_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u64>()
+            .map_err(|e| anyhow::anyhow!("Invalid DB_POOL_TIMEOUT_SECS: {}", e))?;
+
+        let db_statement_timeout_ms = std::env::var("DB_STATEMENT_TIMEOUT_MS")
+            .unwrap_or_else(|_| "30000".to_string())
+            .parse::<u64>()
+            .map_err(|e| anyhow::anyhow!("Invalid DB_STATEMENT_TIMEOUT_MS: {}", e))?;
+
+        let embedding_concurrency = match std::env::var("EMBEDDING_CONCURRENCY") {
+            Ok(value) => value
+                .parse::<usize>()
+                .map_err(|e| anyhow::anyhow!("Invalid EMBEDDING_CONCURRENCY: {}", e))?,
+            Err(_) => std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        };
+        if embedding_concurrency == 0 {
+            return Err(anyhow::anyhow!("EMBEDDING_CONCURRENCY must be at least 1"));
+        }
+
+        let embedder = std::env::var("EMBEDDER").unwrap_or_else(|_| "hashing".to_string());
+
+        let embedding_strip_accents = std::env::var("EMBEDDING_STRIP_ACCENTS")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .map_err(|e| anyhow::anyhow!("Invalid EMBEDDING_STRIP_ACCENTS: {}", e))?;
+
+        let embedding_dimension = std::env::var("EMBEDDING_DIMENSION")
+            .unwrap_or_else(|_| "512".to_string())
+            .parse::<usize>()
+            .map_err(|e| anyhow::anyhow!("Invalid EMBEDDING_DIMENSION: {}", e))?;
+
+        let embedding_char_ngrams = parse_ngram_range(
+            &std::env::var("EMBEDDING_CHAR_NGRAMS").unwrap_or_else(|_| "3".to_string()),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid EMBEDDING_CHAR_NGRAMS: {}", e))?;
+
+        let embedding_word_ngrams = parse_ngram_range(
+            &std::env::var("EMBEDDING_WORD_NGRAMS").unwrap_or_else(|_| "none".to_string()),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid EMBEDDING_WORD_NGRAMS: {}", e))?;
+
+        let embedding_signed_hashing = std::env::var("EMBEDDING_SIGNED_HASHING")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| anyhow::anyhow!("Invalid EMBEDDING_SIGNED_HASHING: {}", e))?;
+
+        let embedding_sublinear_tf = std::env::var("EMBEDDING_SUBLINEAR_TF")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| anyhow::anyhow!("Invalid EMBEDDING_SUBLINEAR_TF: {}", e))?;
+
+        let tfidf_model_path =
+            std::env::var("TFIDF_MODEL_PATH").unwrap_or_else(|_| "tfidf_model.json".to_string());
+
+        let tfidf_min_df = std::env::var("TFIDF_MIN_DF")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse::<usize>()
+            .map_err(|e| anyhow::anyhow!("Invalid TFIDF_MIN_DF: {}", e))?;
+
+        let tfidf_max_df = std::env::var("TFIDF_MAX_DF")
+            .unwrap_or_else(|_| "1.0".to_string())
+            .parse::<f32>()
+            .map_err(|e| anyhow::anyhow!("Invalid TFIDF_MAX_DF: {}", e))?;
+
+        let candle_model_dir =
+            std::env::var("CANDLE_MODEL_DIR").unwrap_or_else(|_| "models/embedding".to_string());
+
         Ok(Config {
             database_url,
             server_host,
             server_port,
+            db_pool_max_size,
+            db_pool_timeout_secs,
+            db_statement_timeout_ms,
+            embedding_concurrency,
+            embedder,
+            embedding_strip_accents,
+            embedding_dimension,
+            embedding_char_ngrams,
+            embedding_word_ngrams,
+            embedding_signed_hashing,
+            embedding_sublinear_tf,
+            tfidf_model_path,
+            tfidf_min_df,
+            tfidf_max_df,
+            candle_model_dir,
         })
     }
 
@@ -74,6 +205,7 @@ mod tests {
         assert!(!config.database_url.is_empty());
         assert!(!config.server_host.is_empty());
         assert!(config.server_port > 0);
+        assert!(config.db_pool_max_size > 0);
     }
 
     #[test]
@@ -82,6 +214,21 @@ mod tests {
             database_url: "test".to_string(),
             server_host: "localhost".to_string(),
             server_port: 3000,
+            db_pool_max_size: 10,
+            db_pool_timeout_secs: 5,
+            db_statement_timeout_ms: 30000,
+            embedding_concurrency: 4,
+            embedder: "hashing".to_string(),
+            embedding_strip_accents: true,
+            embedding_dimension: 512,
+            embedding_char_ngrams: Some((3, 3)),
+            embedding_word_ngrams: None,
+            embedding_signed_hashing: false,
+            embedding_sublinear_tf: false,
+            tfidf_model_path: "tfidf_model.json".to_string(),
+            tfidf_min_df: 1,
+            tfidf_max_df: 1.0,
+            candle_model_dir: "models/embedding".to_string(),
         };
         assert_eq!(config.bind_address(), "localhost:3000");
     }
@@ -1,18 +1,54 @@
+use std::future::{Ready, ready};
+use std::path::Path;
+use std::sync::Arc;
+
 use actix_cors::Cors;
-use actix_web::{App, HttpResponse, HttpServer, Responder, Result, middleware::Logger, post, web};
+use actix_web::dev::Payload;
+use actix_web::{
+    App, FromRequest, HttpRequest, HttpResponse, HttpServer, Responder, Result, delete, get,
+    middleware::Logger, patch, post, web,
+};
+use diesel::{Connection, PgConnection};
 use serde::{Deserialize, Serialize};
 
-mod database;
-mod embedding;
-mod models;
-mod schema;
-
-use database::initialization::establish_connection;
-use database::insertion::{insert_author, insert_book, link_book_to_authors};
-use database::query::{
-    get_authors_by_name, get_books_by_author_name, get_books_by_title, similarity_search_by_prompt,
+use backend::Config;
+use backend::cli::{Command, EmbeddingsAction, MigrateAction, USAGE, parse_args};
+use backend::database::executor::Executor;
+use backend::database::history::{
+    as_actor, get_book_version, get_history, restore_author, restore_book,
+};
+use backend::database::insertion::{
+    AuthorInput, insert_author, insert_book, insert_book_with_authors, link_book_to_authors,
+};
+use backend::database::maintenance::{count_stale_embeddings, fit_tfidf, reembed_stale_books};
+use backend::database::migrations::{
+    migration_status, revert_last_migration, run_pending_migrations, verify_embedding_dimension,
+    verify_schema,
+};
+use backend::database::modification::{
+    delete_author, delete_book, unlink_book_author, update_author, update_book,
+};
+use backend::database::pagination::{PageRequest, Paginated, SortBy, SortOrder};
+use backend::database::pool::create_pool;
+use backend::database::query::{
+    find_similar_books, get_author_by_id, get_authors_by_book_id, get_book_by_id,
+    get_books_by_author_country, get_books_by_author_id, get_books_by_author_name,
+    get_books_by_year, similarity_search_by_prompt,
+};
+use backend::database::search::{
+    DEFAULT_FUZZY_THRESHOLD, SearchCriteria, SearchFilters, fuzzy_search_authors,
+    fuzzy_search_books_by_title, hybrid_search, multi_criteria_search,
+};
+use backend::embedding::vector::norm;
+use backend::embedding::{self, Embedder, TfidfEmbedder};
+use backend::error::{Error, ResultExt};
+use backend::models::{AuthorChanges, BookChanges, BookResponse, NewAuthor, NewBook, RecordTable};
+use backend::params::from_text_or_value;
+use backend::response::ApiResponse;
+use backend::validation::{
+    MAX_ABSTRACT_LENGTH, MAX_COUNTRY_LENGTH, MAX_NAME_LENGTH, MAX_TITLE_LENGTH,
+    MIN_ABSTRACT_LENGTH, Text, Validate, Validator,
 };
-use models::{BookResponse, NewAuthor, NewBook};
 
 #[derive(Deserialize)]
 struct CreateAuthorRequest {
@@ -21,6 +57,29 @@ struct CreateAuthorRequest {
     country: String,
 }
 
+impl CreateAuthorRequest {
+    fn new_author(&self) -> NewAuthor {
+        NewAuthor::new(self.name.trim(), self.birth_year, self.country.trim())
+    }
+}
+
+impl Validate for CreateAuthorRequest {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .text(
+                "name",
+                &self.name,
+                &[Text::NotBlank, Text::MaxLength(MAX_NAME_LENGTH)],
+            )
+            .year("birth_year", self.birth_year)
+            .text(
+                "country",
+                &self.country,
+                &[Text::NotBlank, Text::MaxLength(MAX_COUNTRY_LENGTH)],
+            )
+    }
+}
+
 #[derive(Deserialize)]
 struct CreateBookRequest {
     title: String,
@@ -28,6 +87,135 @@ struct CreateBookRequest {
     abstract_text: String,
 }
 
+impl Validate for CreateBookRequest {
+    fn rules(&self, validator: Validator) -> Validator {
+        validator
+            .text(
+                "title",
+                &self.title,
+                &[Text::NotBlank, Text::MaxLength(MAX_TITLE_LENGTH)],
+            )
+            .year("publication_year", self.publication_year)
+            .text(
+                "abstract_text",
+                &self.abstract_text,
+                &[
+                    Text::MinLength(MIN_ABSTRACT_LENGTH),
+                    Text::MaxLength(MAX_ABSTRACT_LENGTH),
+                ],
+            )
+    }
+}
+
+/// An author of a book created along with it: the ID of an author already
+/// stored, or the fields of a new one
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BookAuthorRequest {
+    Existing { id: i32 },
+    New(CreateAuthorRequest),
+}
+
+#[derive(Deserialize)]
+struct CreateBookWithAuthorsRequest {
+    #[serde(flatten)]
+    book: CreateBookRequest,
+    authors: Vec<BookAuthorRequest>,
+}
+
+impl Validate for CreateBookWithAuthorsRequest {
+    fn rules(&self, validator: Validator) -> Validator {
+        let mut validator = self.book.rules(validator);
+        if self.authors.is_empty() {
+            validator = validator.fail("authors", "must list at least one author");
+        }
+        for (i, author) in self.authors.iter().enumerate() {
+            if let BookAuthorRequest::New(author) = author {
+                validator = validator.nested(&format!("authors[{}]", i), author);
+            }
+        }
+        validator
+    }
+}
+
+/// Fields of an author to change; absent fields are left as they are
+#[derive(Deserialize)]
+struct UpdateAuthorRequest {
+    name: Option<String>,
+    birth_year: Option<i32>,
+    country: Option<String>,
+}
+
+impl UpdateAuthorRequest {
+    fn changes(&self) -> AuthorChanges {
+        AuthorChanges {
+            name: self.name.as_deref().map(|name| name.trim().to_string()),
+            birth_year: self.birth_year,
+            country: self
+                .country
+                .as_deref()
+                .map(|country| country.trim().to_string()),
+        }
+    }
+}
+
+impl Validate for UpdateAuthorRequest {
+    fn rules(&self, mut validator: Validator) -> Validator {
+        if let Some(name) = &self.name {
+            validator = validator.text(
+                "name",
+                name,
+                &[Text::NotBlank, Text::MaxLength(MAX_NAME_LENGTH)],
+            );
+        }
+        if let Some(birth_year) = self.birth_year {
+            validator = validator.year("birth_year", birth_year);
+        }
+        if let Some(country) = &self.country {
+            validator = validator.text(
+                "country",
+                country,
+                &[Text::NotBlank, Text::MaxLength(MAX_COUNTRY_LENGTH)],
+            );
+        }
+        validator
+    }
+}
+
+/// Fields of a book to change; absent fields are left as they are
+#[derive(Deserialize)]
+struct UpdateBookRequest {
+    title: Option<String>,
+    publication_year: Option<i32>,
+    abstract_text: Option<String>,
+}
+
+impl Validate for UpdateBookRequest {
+    fn rules(&self, mut validator: Validator) -> Validator {
+        if let Some(title) = &self.title {
+            validator = validator.text(
+                "title",
+                title,
+                &[Text::NotBlank, Text::MaxLength(MAX_TITLE_LENGTH)],
+            );
+        }
+        if let Some(publication_year) = self.publication_year {
+            validator = validator.year("publication_year", publication_year);
+        }
+        if let Some(abstract_text) = &self.abstract_text {
+            validator = validator.text(
+                "abstract_text",
+                abstract_text,
+                &[
+                    Text::MinLength(MIN_ABSTRACT_LENGTH),
+                    Text::MaxLength(MAX_ABSTRACT_LENGTH),
+                ],
+            );
+        }
+        validator
+    }
+}
+
 #[derive(Deserialize)]
 struct CreateBookAuthorsLinkRequest {
     book_id: i32,
@@ -37,293 +225,901 @@ struct CreateBookAuthorsLinkRequest {
 #[derive(Deserialize)]
 struct SearchAuthorsRequest {
     name: String,
+    /// Minimum trigram word similarity of a fuzzy match, from 0 to 1
+    threshold: Option<f32>,
+    #[serde(flatten)]
+    page: PageParams,
 }
 
 #[derive(Deserialize)]
 struct SearchBooksRequest {
     title: String,
+    /// Minimum trigram word similarity of a fuzzy match, from 0 to 1
+    threshold: Option<f32>,
+    #[serde(flatten)]
+    page: PageParams,
 }
 
 #[derive(Deserialize)]
 struct SearchBooksByAuthorRequest {
     author_name: String,
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+/// Page and order of a list endpoint
+#[derive(Deserialize)]
+struct PageParams {
+    #[serde(default, deserialize_with = "from_text_or_value")]
+    limit: Option<i64>,
+    /// `next_cursor` of the previous page
+    cursor: Option<String>,
+    sort: Option<SortBy>,
+    order: Option<SortOrder>,
+    /// Also return the number of results across all pages
+    #[serde(default, deserialize_with = "from_text_or_value")]
+    with_total: Option<bool>,
+}
+
+impl PageParams {
+    /// The requested page, or why it is invalid
+    fn page_request(self, default_sort: SortBy) -> Result<PageRequest, Error> {
+        let limit = self.limit.unwrap_or(10);
+        if limit <= 0 || limit > 100 {
+            return Err(invalid("Limit must be between 1 and 100"));
+        }
+        let sort = self.sort.unwrap_or(default_sort);
+        Ok(PageRequest {
+            limit,
+            cursor: self.cursor,
+            sort,
+            order: self.order.unwrap_or(sort.default_order()),
+            with_total: self.with_total.unwrap_or(false),
+        })
+    }
+}
+
+/// Either an exact `year` or a `year_from`..`year_to` range; an open end
+/// of the range is unbounded
+#[derive(Deserialize)]
+struct SearchBooksByYearRequest {
+    year: Option<i32>,
+    year_from: Option<i32>,
+    year_to: Option<i32>,
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+#[derive(Deserialize)]
+struct SearchBooksByCountryRequest {
+    country: String,
+    #[serde(flatten)]
+    page: PageParams,
+}
+
+#[derive(Deserialize)]
+struct MultiCriteriaSearchRequest {
+    title: Option<String>,
+    content: Option<String>,
+    #[serde(flatten)]
+    filters: SearchFilters,
+    /// Minimum trigram word similarity of fuzzy title and author matches
+    threshold: Option<f32>,
+    limit: Option<i32>,
+}
+
+/// How `/search/book/embedding` matches books against the query
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum SearchMode {
+    /// Cosine similarity between embeddings only
+    #[default]
+    Vector,
+    /// Full-text and vector search merged with reciprocal rank fusion
+    Hybrid,
 }
 
 #[derive(Deserialize)]
 struct EmbeddingSearchRequest {
     query: String,
     limit: Option<i32>,
+    #[serde(default)]
+    mode: SearchMode,
+    /// Leave out results whose cosine similarity is below this value
+    min_score: Option<f32>,
+    #[serde(flatten)]
+    filters: SearchFilters,
 }
 
-#[derive(Serialize)]
-struct ApiResponse<T> {
-    success: bool,
-    data: Option<T>,
-    message: String,
+#[derive(Deserialize)]
+struct SimilarBooksQuery {
+    limit: Option<i32>,
+    /// Also leave out books sharing an author with the source book
+    exclude_same_authors: Option<bool>,
 }
 
-impl<T> ApiResponse<T> {
-    fn success(data: T) -> Self {
-        ApiResponse {
-            success: true,
-            data: Some(data),
-            message: "Success".to_string(),
-        }
-    }
+/// Fails validation with `message`
+fn invalid(message: &str) -> Error {
+    Error::Validation(message.to_string())
+}
 
-    fn error(message: String) -> ApiResponse<()> {
-        ApiResponse {
-            success: false,
-            data: None,
-            message,
-        }
+/// Header naming who makes a change, recorded in the history of the
+/// authors and books it touches
+const ACTOR_HEADER: &str = "X-Actor";
+
+/// Actor of changes made without the [`ACTOR_HEADER`]
+const ANONYMOUS_ACTOR: &str = "anonymous";
+
+const MAX_ACTOR_LENGTH: usize = 100;
+
+/// Who makes the change a request asks for, from the [`ACTOR_HEADER`]
+struct Actor(String);
+
+impl FromRequest for Actor {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let actor = match req.headers().get(ACTOR_HEADER) {
+            None => Ok(Actor(ANONYMOUS_ACTOR.to_string())),
+            Some(value) => match value.to_str().map(str::trim) {
+                Ok(actor) if !actor.is_empty() && actor.chars().count() <= MAX_ACTOR_LENGTH => {
+                    Ok(Actor(actor.to_string()))
+                }
+                _ => Err(Error::BadRequest(format!(
+                    "{} must be a name of 1 to {} characters",
+                    ACTOR_HEADER, MAX_ACTOR_LENGTH
+                ))
+                .into()),
+            },
+        };
+        ready(actor)
     }
 }
 
+/// Answers a list endpoint with one page of it
+fn page_response<T: Serialize>(page: Paginated<T>) -> HttpResponse {
+    HttpResponse::Ok().json(ApiResponse::page(page))
+}
+
 #[post("/insert/author")]
-async fn create_author(req: web::Json<CreateAuthorRequest>) -> Result<impl Responder> {
-    let mut connection = match establish_connection() {
-        // Correctly handle the Result
-        Ok(conn) => conn,
-        Err(e) => {
-            log::error!("Failed to establish database connection: {}", e);
-            return Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to connect to database: {}",
-                    e
-                ))),
-            );
-        }
-    };
-    let new_author = NewAuthor::new(&req.name, req.birth_year, &req.country);
+async fn create_author(
+    executor: web::Data<Executor>,
+    req: web::Json<CreateAuthorRequest>,
+    actor: Actor,
+) -> Result<impl Responder> {
+    req.validate()?;
+    let new_author = req.new_author();
 
-    match insert_author(&mut connection, &new_author) {
-        // Pass mutable reference
-        Ok(author) => Ok(HttpResponse::Created().json(ApiResponse::success(author))),
-        Err(e) => {
-            log::error!("Failed to create author: {}", e);
-            Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to create author: {}",
-                    e
-                ))),
+    let author = executor
+        .run(move |conn| as_actor(conn, &actor.0, |conn| insert_author(conn, &new_author)))
+        .await?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(author)))
+}
+
+/// Embeds the abstract of a book to create, off the actix worker threads
+async fn embed_book(
+    executor: &Executor,
+    embedder: web::Data<dyn Embedder>,
+    req: CreateBookRequest,
+) -> Result<NewBook, Error> {
+    let embedder = embedder.into_inner();
+    executor
+        .embed(move || {
+            let new_book = NewBook::new(
+                req.title.trim(),
+                req.publication_year,
+                req.abstract_text.trim(),
+                embedder.as_ref(),
             )
-        }
-    }
+            .context("Failed to embed book abstract")?;
+            if new_book.embedding.as_ref().is_none_or(|e| norm(e) == 0.0) {
+                Validator::new()
+                    .fail("abstract_text", "has no words to embed")
+                    .finish()?;
+            }
+            Ok::<_, Error>(new_book)
+        })
+        .await
 }
 
 #[post("/insert/book")]
-async fn create_book(req: web::Json<CreateBookRequest>) -> Result<impl Responder> {
-    let mut connection = match establish_connection() {
-        // Correctly handle the Result
-        Ok(conn) => conn,
-        Err(e) => {
-            log::error!("Failed to establish database connection: {}", e);
-            return Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to connect to database: {}",
-                    e
-                ))),
-            );
-        }
-    };
+async fn create_book(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
+    req: web::Json<CreateBookRequest>,
+    actor: Actor,
+) -> Result<impl Responder> {
+    req.validate()?;
+    let new_book = embed_book(&executor, embedder, req.into_inner()).await?;
 
-    let new_book = match NewBook::new(&req.title, req.publication_year, &req.abstract_text) {
-        Ok(book) => book,
-        Err(e) => {
-            log::error!("Failed to create book: {}", e);
-            return Ok(
-                HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
-                    "Failed to create book: {}",
-                    e
-                ))),
-            );
-        }
-    };
+    let book = executor
+        .run(move |conn| as_actor(conn, &actor.0, |conn| insert_book(conn, &new_book)))
+        .await?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(BookResponse::from(book))))
+}
 
-    match insert_book(&mut connection, &new_book) {
-        // Pass mutable reference
-        Ok(book) => {
-            Ok(HttpResponse::Created().json(ApiResponse::success(BookResponse::from(book))))
-        }
-        Err(e) => {
-            log::error!("Failed to create book with author: {}", e);
-            Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to create book with author: {}",
-                    e
-                ))),
-            )
-        }
-    }
+#[post("/insert/book-with-authors")]
+async fn create_book_with_authors(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
+    req: web::Json<CreateBookWithAuthorsRequest>,
+    actor: Actor,
+) -> Result<impl Responder> {
+    req.validate()?;
+    let req = req.into_inner();
+    let authors: Vec<AuthorInput> = req
+        .authors
+        .iter()
+        .map(|author| match author {
+            BookAuthorRequest::Existing { id } => AuthorInput::Existing(*id),
+            BookAuthorRequest::New(author) => AuthorInput::New(author.new_author()),
+        })
+        .collect();
+    let new_book = embed_book(&executor, embedder, req.book).await?;
+
+    let book = executor
+        .run(move |conn| {
+            as_actor(conn, &actor.0, |conn| {
+                insert_book_with_authors(conn, &new_book, &authors)
+            })
+        })
+        .await?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(book)))
 }
 
 #[post("/insert/book-author-link")]
 async fn create_book_author_link(
+    executor: web::Data<Executor>,
     req: web::Json<CreateBookAuthorsLinkRequest>,
+    actor: Actor,
 ) -> Result<impl Responder> {
-    let mut connection = match establish_connection() {
-        // Correctly handle the Result
-        Ok(conn) => conn,
-        Err(e) => {
-            log::error!("Failed to establish database connection: {}", e);
-            return Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to connect to database: {}",
-                    e
-                ))),
-            );
-        }
-    };
+    let req = req.into_inner();
 
-    match link_book_to_authors(&mut connection, req.book_id, &req.authors_ids) {
-        // Pass mutable reference
-        Ok(book_authors) => Ok(HttpResponse::Created().json(ApiResponse::success(book_authors))),
-        Err(e) => {
-            log::error!("Failed to link book to authors: {}", e);
-            Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to link book to authors: {}",
-                    e
-                ))),
-            )
-        }
+    let book_authors = executor
+        .run(move |conn| {
+            as_actor(conn, &actor.0, |conn| {
+                link_book_to_authors(conn, req.book_id, &req.authors_ids)
+            })
+        })
+        .await?;
+    Ok(HttpResponse::Created().json(ApiResponse::success(book_authors)))
+}
+
+async fn run_search_authors(
+    executor: web::Data<Executor>,
+    req: SearchAuthorsRequest,
+) -> Result<HttpResponse> {
+    let threshold = req.threshold.unwrap_or(DEFAULT_FUZZY_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(invalid("threshold must be between 0 and 1").into());
     }
+    let page = req.page.page_request(SortBy::Relevance)?;
+
+    let authors = executor
+        .run(move |conn| fuzzy_search_authors(conn, &req.name, threshold, &page))
+        .await?;
+    Ok(page_response(authors))
 }
 
 #[post("/search/authors")]
-async fn search_authors(req: web::Json<SearchAuthorsRequest>) -> Result<impl Responder> {
-    let mut connection = match establish_connection() {
-        // Correctly handle the Result
-        Ok(conn) => conn,
-        Err(e) => {
-            log::error!("Failed to establish database connection: {}", e);
-            return Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to connect to database: {}",
-                    e
-                ))),
-            );
-        }
-    };
+async fn search_authors(
+    executor: web::Data<Executor>,
+    req: web::Json<SearchAuthorsRequest>,
+) -> Result<impl Responder> {
+    run_search_authors(executor, req.into_inner()).await
+}
 
-    match get_authors_by_name(&mut connection, &req.name) {
-        // Pass mutable reference
-        Ok(authors) => Ok(HttpResponse::Ok().json(ApiResponse::success(authors))),
-        Err(e) => {
-            log::error!("Failed to search authors: {}", e);
-            Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to search authors: {}",
-                    e
-                ))),
-            )
-        }
+#[get("/search/authors")]
+async fn search_authors_get(
+    executor: web::Data<Executor>,
+    req: web::Query<SearchAuthorsRequest>,
+) -> Result<impl Responder> {
+    run_search_authors(executor, req.into_inner()).await
+}
+
+async fn run_search_books(
+    executor: web::Data<Executor>,
+    req: SearchBooksRequest,
+) -> Result<HttpResponse> {
+    let threshold = req.threshold.unwrap_or(DEFAULT_FUZZY_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(invalid("threshold must be between 0 and 1").into());
     }
+    let page = req.page.page_request(SortBy::Relevance)?;
+
+    let books = executor
+        .run(move |conn| fuzzy_search_books_by_title(conn, &req.title, threshold, &page))
+        .await?;
+    Ok(page_response(books))
 }
 
 #[post("/search/books")]
-async fn search_books(req: web::Json<SearchBooksRequest>) -> Result<impl Responder> {
-    let mut connection = match establish_connection() {
-        // Correctly handle the Result
-        Ok(conn) => conn,
-        Err(e) => {
-            log::error!("Failed to establish database connection: {}", e);
-            return Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to connect to database: {}",
-                    e
-                ))),
-            );
-        }
-    };
+async fn search_books(
+    executor: web::Data<Executor>,
+    req: web::Json<SearchBooksRequest>,
+) -> Result<impl Responder> {
+    run_search_books(executor, req.into_inner()).await
+}
 
-    match get_books_by_title(&mut connection, &req.title) {
-        // Pass mutable reference
-        Ok(books) => Ok(HttpResponse::Ok().json(ApiResponse::success(books))),
-        Err(e) => {
-            log::error!("Failed to search books: {}", e);
-            Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to search books: {}",
-                    e
-                ))),
-            )
-        }
-    }
+#[get("/search/books")]
+async fn search_books_get(
+    executor: web::Data<Executor>,
+    req: web::Query<SearchBooksRequest>,
+) -> Result<impl Responder> {
+    run_search_books(executor, req.into_inner()).await
+}
+
+async fn run_search_books_by_author(
+    executor: web::Data<Executor>,
+    req: SearchBooksByAuthorRequest,
+) -> Result<HttpResponse> {
+    let page = req.page.page_request(SortBy::Title)?;
+
+    let books = executor
+        .run(move |conn| get_books_by_author_name(conn, &req.author_name, &page))
+        .await?;
+    Ok(page_response(books))
 }
 
 #[post("/search/author/books")]
 async fn search_books_by_author(
+    executor: web::Data<Executor>,
     req: web::Json<SearchBooksByAuthorRequest>,
 ) -> Result<impl Responder> {
-    let mut connection = match establish_connection() {
-        // Correctly handle the Result
-        Ok(conn) => conn,
-        Err(e) => {
-            log::error!("Failed to establish database connection: {}", e);
-            return Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to connect to database: {}",
-                    e
-                ))),
-            );
-        }
+    run_search_books_by_author(executor, req.into_inner()).await
+}
+
+#[get("/search/author/books")]
+async fn search_books_by_author_get(
+    executor: web::Data<Executor>,
+    req: web::Query<SearchBooksByAuthorRequest>,
+) -> Result<impl Responder> {
+    run_search_books_by_author(executor, req.into_inner()).await
+}
+
+async fn run_search_books_by_year(
+    executor: web::Data<Executor>,
+    req: SearchBooksByYearRequest,
+) -> Result<HttpResponse> {
+    let (year_from, year_to) = match (req.year, req.year_from, req.year_to) {
+        (Some(year), None, None) => (year, year),
+        (None, Some(_), _) | (None, _, Some(_)) => (
+            req.year_from.unwrap_or(i32::MIN),
+            req.year_to.unwrap_or(i32::MAX),
+        ),
+        _ => return Err(invalid("Give either year or year_from/year_to").into()),
     };
+    if year_from > year_to {
+        return Err(invalid("year_from must not be after year_to").into());
+    }
+    let page = req.page.page_request(SortBy::Title)?;
 
-    match get_books_by_author_name(&mut connection, &req.author_name) {
-        // Pass mutable reference
-        Ok(books) => Ok(HttpResponse::Ok().json(ApiResponse::success(books))),
-        Err(e) => {
-            log::error!("Failed to search books by author: {}", e);
-            Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to search books by author: {}",
-                    e
-                ))),
+    let books = executor
+        .run(move |conn| get_books_by_year(conn, year_from, year_to, &page))
+        .await?;
+    Ok(page_response(books))
+}
+
+#[post("/search/year/books")]
+async fn search_books_by_year(
+    executor: web::Data<Executor>,
+    req: web::Json<SearchBooksByYearRequest>,
+) -> Result<impl Responder> {
+    run_search_books_by_year(executor, req.into_inner()).await
+}
+
+#[get("/search/year/books")]
+async fn search_books_by_year_get(
+    executor: web::Data<Executor>,
+    req: web::Query<SearchBooksByYearRequest>,
+) -> Result<impl Responder> {
+    run_search_books_by_year(executor, req.into_inner()).await
+}
+
+async fn run_search_books_by_country(
+    executor: web::Data<Executor>,
+    req: SearchBooksByCountryRequest,
+) -> Result<HttpResponse> {
+    let page = req.page.page_request(SortBy::Title)?;
+
+    let books = executor
+        .run(move |conn| get_books_by_author_country(conn, req.country.trim(), &page))
+        .await?;
+    Ok(page_response(books))
+}
+
+#[post("/search/country/books")]
+async fn search_books_by_country(
+    executor: web::Data<Executor>,
+    req: web::Json<SearchBooksByCountryRequest>,
+) -> Result<impl Responder> {
+    run_search_books_by_country(executor, req.into_inner()).await
+}
+
+#[get("/search/country/books")]
+async fn search_books_by_country_get(
+    executor: web::Data<Executor>,
+    req: web::Query<SearchBooksByCountryRequest>,
+) -> Result<impl Responder> {
+    run_search_books_by_country(executor, req.into_inner()).await
+}
+
+#[get("/authors/{id}")]
+async fn get_author(executor: web::Data<Executor>, path: web::Path<i32>) -> Result<impl Responder> {
+    let author_id = path.into_inner();
+
+    let author = executor
+        .run(move |conn| get_author_by_id(conn, author_id))
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(author)))
+}
+
+#[get("/books/{id}")]
+async fn get_book(executor: web::Data<Executor>, path: web::Path<i32>) -> Result<impl Responder> {
+    let book_id = path.into_inner();
+
+    let book = executor
+        .run(move |conn| get_book_by_id(conn, book_id))
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(book)))
+}
+
+#[get("/authors/{id}/books")]
+async fn get_author_books(
+    executor: web::Data<Executor>,
+    path: web::Path<i32>,
+    query: web::Query<PageParams>,
+) -> Result<impl Responder> {
+    let author_id = path.into_inner();
+    let page = query.into_inner().page_request(SortBy::Year)?;
+
+    let books = executor
+        .run(move |conn| get_books_by_author_id(conn, author_id, &page))
+        .await?;
+    Ok(page_response(books))
+}
+
+#[get("/books/{id}/authors")]
+async fn get_book_authors(
+    executor: web::Data<Executor>,
+    path: web::Path<i32>,
+) -> Result<impl Responder> {
+    let book_id = path.into_inner();
+
+    let authors = executor
+        .run(move |conn| get_authors_by_book_id(conn, book_id))
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(authors)))
+}
+
+#[patch("/authors/{id}")]
+async fn update_author_fields(
+    executor: web::Data<Executor>,
+    path: web::Path<i32>,
+    req: web::Json<UpdateAuthorRequest>,
+    actor: Actor,
+) -> Result<impl Responder> {
+    let author_id = path.into_inner();
+    req.validate()?;
+    let changes = req.changes();
+    if changes.is_empty() {
+        return Err(invalid("Give at least one field to change").into());
+    }
+
+    let author = executor
+        .run(move |conn| {
+            as_actor(conn, &actor.0, |conn| {
+                update_author(conn, author_id, &changes)
+            })
+        })
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(author)))
+}
+
+#[patch("/books/{id}")]
+async fn update_book_fields(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
+    path: web::Path<i32>,
+    req: web::Json<UpdateBookRequest>,
+    actor: Actor,
+) -> Result<impl Responder> {
+    let book_id = path.into_inner();
+    req.validate()?;
+    let req = req.into_inner();
+    if req.title.is_none() && req.publication_year.is_none() && req.abstract_text.is_none() {
+        return Err(invalid("Give at least one field to change").into());
+    }
+
+    // A new abstract is embedded again, off the actix worker threads
+    let embedder = embedder.into_inner();
+    let changes = executor
+        .embed(move || {
+            let changes = BookChanges::new(
+                req.title.as_deref().map(str::trim),
+                req.publication_year,
+                req.abstract_text.as_deref().map(str::trim),
+                embedder.as_ref(),
             )
-        }
+            .context("Failed to embed book abstract")?;
+            if req.abstract_text.is_some()
+                && changes.embedding.as_ref().is_none_or(|e| norm(e) == 0.0)
+            {
+                Validator::new()
+                    .fail("abstract_text", "has no words to embed")
+                    .finish()?;
+            }
+            Ok::<_, Error>(changes)
+        })
+        .await?;
+
+    let book = executor
+        .run(move |conn| as_actor(conn, &actor.0, |conn| update_book(conn, book_id, &changes)))
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(book)))
+}
+
+#[delete("/authors/{id}")]
+async fn delete_author_record(
+    executor: web::Data<Executor>,
+    path: web::Path<i32>,
+    actor: Actor,
+) -> Result<impl Responder> {
+    let author_id = path.into_inner();
+
+    let author = executor
+        .run(move |conn| as_actor(conn, &actor.0, |conn| delete_author(conn, author_id)))
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(author)))
+}
+
+#[delete("/books/{id}")]
+async fn delete_book_record(
+    executor: web::Data<Executor>,
+    path: web::Path<i32>,
+    actor: Actor,
+) -> Result<impl Responder> {
+    let book_id = path.into_inner();
+
+    let book = executor
+        .run(move |conn| as_actor(conn, &actor.0, |conn| delete_book(conn, book_id)))
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(book)))
+}
+
+#[delete("/books/{book_id}/authors/{author_id}")]
+async fn delete_book_author_link(
+    executor: web::Data<Executor>,
+    path: web::Path<(i32, i32)>,
+    actor: Actor,
+) -> Result<impl Responder> {
+    let (book_id, author_id) = path.into_inner();
+
+    let link = executor
+        .run(move |conn| {
+            as_actor(conn, &actor.0, |conn| {
+                unlink_book_author(conn, book_id, author_id)
+            })
+        })
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(link)))
+}
+
+#[get("/authors/{id}/history")]
+async fn get_author_history(
+    executor: web::Data<Executor>,
+    path: web::Path<i32>,
+) -> Result<impl Responder> {
+    let author_id = path.into_inner();
+
+    let history = executor
+        .run(move |conn| get_history(conn, RecordTable::Authors, author_id))
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(history)))
+}
+
+#[get("/books/{id}/history")]
+async fn get_book_history(
+    executor: web::Data<Executor>,
+    path: web::Path<i32>,
+) -> Result<impl Responder> {
+    let book_id = path.into_inner();
+
+    let history = executor
+        .run(move |conn| get_history(conn, RecordTable::Books, book_id))
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(history)))
+}
+
+#[post("/authors/{id}/history/{version}/restore")]
+async fn restore_author_version(
+    executor: web::Data<Executor>,
+    path: web::Path<(i32, i32)>,
+    actor: Actor,
+) -> Result<impl Responder> {
+    let (author_id, version) = path.into_inner();
+
+    let author = executor
+        .run(move |conn| {
+            as_actor(conn, &actor.0, |conn| {
+                restore_author(conn, author_id, version)
+            })
+        })
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(author)))
+}
+
+#[post("/books/{id}/history/{version}/restore")]
+async fn restore_book_version(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
+    path: web::Path<(i32, i32)>,
+    actor: Actor,
+) -> Result<impl Responder> {
+    let (book_id, version) = path.into_inner();
+
+    let fields = executor
+        .run(move |conn| get_book_version(conn, book_id, version))
+        .await?;
+
+    // The abstract of the version is embedded again, off the actix worker
+    // threads and before the book is locked
+    let embedder = embedder.into_inner();
+    let changes = executor
+        .embed(move || fields.changes(embedder.as_ref()))
+        .await?;
+
+    let book = executor
+        .run(move |conn| as_actor(conn, &actor.0, |conn| restore_book(conn, book_id, &changes)))
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(book)))
+}
+
+async fn run_search_books_by_embedding(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
+    req: EmbeddingSearchRequest,
+) -> Result<HttpResponse> {
+    let embedder = embedder.into_inner();
+
+    let limit = req.limit.unwrap_or(10);
+    if limit <= 0 || limit > 100 {
+        return Err(invalid("Limit must be between 1 and 100").into());
     }
+
+    if let Some(min_score) = req.min_score
+        && !(-1.0..=1.0).contains(&min_score)
+    {
+        return Err(invalid("min_score must be between -1 and 1").into());
+    }
+
+    let response = match req.mode {
+        SearchMode::Vector => executor
+            .run_embedding(move |conn| {
+                similarity_search_by_prompt(
+                    conn,
+                    embedder.as_ref(),
+                    &req.query,
+                    limit,
+                    req.min_score,
+                    &req.filters,
+                )
+            })
+            .await
+            .map(|books| HttpResponse::Ok().json(ApiResponse::success(books))),
+        SearchMode::Hybrid => executor
+            .run_embedding(move |conn| {
+                hybrid_search(
+                    conn,
+                    embedder.as_ref(),
+                    &req.query,
+                    limit,
+                    req.min_score,
+                    &req.filters,
+                )
+            })
+            .await
+            .map(|books| HttpResponse::Ok().json(ApiResponse::success(books))),
+    };
+    Ok(response?)
 }
 
 #[post("/search/book/embedding")]
 async fn search_books_by_embedding(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
     req: web::Json<EmbeddingSearchRequest>,
 ) -> Result<impl Responder> {
-    let mut connection = match establish_connection() {
-        // Correctly handle the Result
-        Ok(conn) => conn,
-        Err(e) => {
-            log::error!("Failed to establish database connection: {}", e);
-            return Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to connect to database: {}",
-                    e
-                ))),
-            );
-        }
-    };
+    run_search_books_by_embedding(executor, embedder, req.into_inner()).await
+}
+
+#[get("/search/book/embedding")]
+async fn search_books_by_embedding_get(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
+    req: web::Query<EmbeddingSearchRequest>,
+) -> Result<impl Responder> {
+    run_search_books_by_embedding(executor, embedder, req.into_inner()).await
+}
+
+async fn run_search(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
+    req: MultiCriteriaSearchRequest,
+) -> Result<HttpResponse> {
+    let embedder = embedder.into_inner();
 
     let limit = req.limit.unwrap_or(10);
     if limit <= 0 || limit > 100 {
-        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-            "Limit must be between 1 and 100".to_string(),
-        )));
+        return Err(invalid("Limit must be between 1 and 100").into());
+    }
+    let threshold = req.threshold.unwrap_or(DEFAULT_FUZZY_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(invalid("threshold must be between 0 and 1").into());
     }
 
-    match similarity_search_by_prompt(&mut connection, &req.query, limit) {
-        // Pass mutable reference
-        Ok(books) => Ok(HttpResponse::Ok().json(ApiResponse::success(books))),
-        Err(e) => {
-            log::error!("Failed to search books by embedding: {}", e);
-            Ok(
-                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(format!(
-                    "Failed to search books by embedding: {}",
-                    e
-                ))),
+    let criteria_has_content = req.content.is_some();
+    let criteria = SearchCriteria {
+        title: req.title,
+        content: req.content,
+        filters: req.filters,
+        threshold,
+        limit,
+    };
+    let query =
+        move |conn: &mut PgConnection| multi_criteria_search(conn, embedder.as_ref(), &criteria);
+    // Only content needs an embedding, and with it an embedding permit
+    let books = if criteria_has_content {
+        executor.run_embedding(query).await?
+    } else {
+        executor.run(query).await?
+    };
+    Ok(HttpResponse::Ok().json(ApiResponse::success(books)))
+}
+
+#[post("/search")]
+async fn search(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
+    req: web::Json<MultiCriteriaSearchRequest>,
+) -> Result<impl Responder> {
+    run_search(executor, embedder, req.into_inner()).await
+}
+
+#[get("/search")]
+async fn search_get(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
+    req: web::Query<MultiCriteriaSearchRequest>,
+) -> Result<impl Responder> {
+    run_search(executor, embedder, req.into_inner()).await
+}
+
+#[get("/books/{id}/similar")]
+async fn get_similar_books(
+    executor: web::Data<Executor>,
+    embedder: web::Data<dyn Embedder>,
+    path: web::Path<i32>,
+    query: web::Query<SimilarBooksQuery>,
+) -> Result<impl Responder> {
+    let book_id = path.into_inner();
+    let query = query.into_inner();
+    let embedder = embedder.into_inner();
+
+    let limit = query.limit.unwrap_or(10);
+    if limit <= 0 || limit > 100 {
+        return Err(invalid("Limit must be between 1 and 100").into());
+    }
+    let exclude_same_authors = query.exclude_same_authors.unwrap_or(false);
+
+    let books = executor
+        .run_embedding(move |conn| {
+            find_similar_books(
+                conn,
+                embedder.as_ref(),
+                book_id,
+                limit,
+                exclude_same_authors,
             )
+        })
+        .await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(books)))
+}
+
+/// Answers a malformed JSON body, query string or path with a 400
+fn extractor_error(error: impl std::fmt::Display) -> actix_web::Error {
+    Error::BadRequest(error.to_string()).into()
+}
+
+/// Runs a `migrate` action over a dedicated connection, so long-running
+/// migrations are not subject to the pool's statement timeout
+fn run_migrate_command(config: &Config, action: MigrateAction) -> anyhow::Result<()> {
+    let mut conn = PgConnection::establish(&config.database_url)
+        .map_err(|e| anyhow::anyhow!("Failed to connect to database: {}", e))?;
+
+    match action {
+        MigrateAction::Up => {
+            let applied = run_pending_migrations(&mut conn)?;
+            if applied.is_empty() {
+                log::info!("No pending migrations");
+            }
+            for version in applied {
+                log::info!("Applied migration {}", version);
+            }
+        }
+        MigrateAction::Down => {
+            let version = revert_last_migration(&mut conn)?;
+            log::info!("Reverted migration {}", version);
+        }
+        MigrateAction::Status => {
+            for status in migration_status(&mut conn)? {
+                let mark = if status.applied { "X" } else { " " };
+                println!("[{}] {}", mark, status.version);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds the configured embedder and logs which one is in use
+fn load_embedder(config: &Config) -> anyhow::Result<Arc<dyn Embedder>> {
+    let embedder = embedding::from_config(config)
+        .map_err(|e| anyhow::anyhow!("Failed to create embedder: {}", e))?;
+    log::info!(
+        "Using embedder {} ({} dimensions)",
+        embedder.version(),
+        embedder.dimension()
+    );
+    Ok(embedder)
+}
+
+/// Runs an `embeddings` action over a dedicated connection
+fn run_embeddings_command(config: &Config, action: EmbeddingsAction) -> anyhow::Result<()> {
+    let mut conn = PgConnection::establish(&config.database_url)
+        .map_err(|e| anyhow::anyhow!("Failed to connect to database: {}", e))?;
+
+    let embedder = match action {
+        EmbeddingsAction::Reembed => load_embedder(config)?,
+        EmbeddingsAction::Refit => {
+            if config.embedder != TfidfEmbedder::NAME {
+                return Err(anyhow::anyhow!(
+                    "`embeddings refit` requires EMBEDDER={} (currently {})",
+                    TfidfEmbedder::NAME,
+                    config.embedder
+                ));
+            }
+
+            let embedder = fit_tfidf(&mut conn, embedding::tfidf_options(config))?;
+            embedder.save(Path::new(&config.tfidf_model_path))?;
+            log::info!(
+                "Fitted {} over {} abstract(s) with {} term(s), saved to {}",
+                embedder.version(),
+                embedder.document_count(),
+                embedder.vocabulary_size(),
+                config.tfidf_model_path
+            );
+            Arc::new(embedder)
         }
+    };
+
+    // A refit changes the embedder version, so every book is stale afterwards
+    let report = reembed_stale_books(&mut conn, embedder.as_ref())?;
+    log::info!(
+        "Re-embedded {} book(s) with {}",
+        report.updated,
+        embedder.version()
+    );
+    if report.without_embedding > 0 {
+        log::warn!(
+            "{} book(s) embed to a zero vector and were stored without an embedding; \
+             they are left out of vector search",
+            report.without_embedding
+        );
     }
+
+    Ok(())
 }
 
 #[actix_web::main]
@@ -331,19 +1127,110 @@ async fn main() -> std::io::Result<()> {
     // Initialize logger
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
-    // Test database connection
-    match database::initialization::establish_connection() {
-        Ok(_) => log::info!("Database connection test successful"),
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let migrate = match command {
+        Command::Help => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Command::Migrate(action) => {
+            if let Err(e) = run_migrate_command(&config, action) {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::Embeddings(action) => {
+            if let Err(e) = run_embeddings_command(&config, action) {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::Serve { migrate } => migrate,
+    };
+
+    if migrate && let Err(e) = run_migrate_command(&config, MigrateAction::Up) {
+        log::error!("{}", e);
+        std::process::exit(1);
+    }
+
+    let embedder = match load_embedder(&config) {
+        Ok(embedder) => embedder,
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Create the connection pool once; this also verifies the database is reachable
+    let pool = match create_pool(&config) {
+        Ok(pool) => {
+            log::info!(
+                "Database connection pool ready (max size {})",
+                config.db_pool_max_size
+            );
+            pool
+        }
         Err(e) => {
             log::error!("Database connection test failed: {}", e);
             std::process::exit(1);
         }
+    };
+
+    // Refuse to serve against a database whose schema doesn't match this binary
+    let schema_check = pool
+        .get()
+        .map_err(|e| anyhow::anyhow!("Failed to check out connection: {}", e))
+        .and_then(|mut conn| {
+            verify_schema(&mut conn)?;
+            verify_embedding_dimension(&mut conn, embedder.as_ref())?;
+            count_stale_embeddings(&mut conn, embedder.as_ref())
+        });
+    match schema_check {
+        Ok(stale) => {
+            if stale > 0 {
+                log::warn!(
+                    "{} book(s) have embeddings from another embedder version and are \
+                     excluded from vector search; run `backend embeddings reembed` to \
+                     refresh them",
+                    stale
+                );
+            }
+        }
+        Err(e) => {
+            log::error!("Database schema check failed: {}", e);
+            std::process::exit(1);
+        }
     }
 
-    let bind_address = "127.0.0.1:8080";
+    let embedder: web::Data<dyn Embedder> = web::Data::from(embedder);
+    let executor = web::Data::new(Executor::new(pool, config.embedding_concurrency));
+
+    let bind_address = config.bind_address();
     log::info!("Starting server at http://{}", bind_address);
     HttpServer::new(move || {
         App::new()
+            .app_data(executor.clone())
+            .app_data(embedder.clone())
+            .app_data(web::JsonConfig::default().error_handler(|e, _| extractor_error(e)))
+            .app_data(web::QueryConfig::default().error_handler(|e, _| extractor_error(e)))
+            .app_data(web::PathConfig::default().error_handler(|e, _| extractor_error(e)))
             .wrap(Logger::default())
             .wrap(
                 Cors::default()
@@ -354,13 +1241,38 @@ async fn main() -> std::io::Result<()> {
             )
             .service(create_author)
             .service(create_book)
+            .service(create_book_with_authors)
             .service(create_book_author_link)
             .service(search_authors)
+            .service(search_authors_get)
             .service(search_books)
+            .service(search_books_get)
             .service(search_books_by_author)
+            .service(search_books_by_author_get)
+            .service(search_books_by_year)
+            .service(search_books_by_year_get)
+            .service(search_books_by_country)
+            .service(search_books_by_country_get)
             .service(search_books_by_embedding)
+            .service(search_books_by_embedding_get)
+            .service(search)
+            .service(search_get)
+            .service(get_author)
+            .service(get_book)
+            .service(get_author_books)
+            .service(get_book_authors)
+            .service(get_similar_books)
+            .service(update_author_fields)
+            .service(update_book_fields)
+            .service(delete_author_record)
+            .service(delete_book_record)
+            .service(delete_book_author_link)
+            .service(get_author_history)
+            .service(get_book_history)
+            .service(restore_author_version)
+            .service(restore_book_version)
     })
-    .bind(bind_address)?
+    .bind(&bind_address)?
     .run()
     .await
 }
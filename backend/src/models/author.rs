@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use serde::Serialize;
 
@@ -11,6 +12,20 @@ pub struct Author {
     pub name: String,
     pub birth_year: i32,
     pub country: String,
+    pub created_at: DateTime<Utc>,
+    /// Last change to a field or to whether the author is deleted
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// The author fields embedded in book responses
+#[derive(Queryable, Selectable, PartialEq, Debug, Clone, Serialize)]
+#[diesel(table_name = authors)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AuthorSummary {
+    pub id: i32,
+    pub name: String,
+    pub country: String,
 }
 
 #[derive(Insertable, Debug)]
@@ -31,6 +46,22 @@ impl NewAuthor {
     }
 }
 
+/// Fields of an author to change; `None` leaves a field as it is
+#[derive(AsChangeset, Debug, Default)]
+#[diesel(table_name = authors)]
+pub struct AuthorChanges {
+    pub name: Option<String>,
+    pub birth_year: Option<i32>,
+    pub country: Option<String>,
+}
+
+impl AuthorChanges {
+    /// Whether nothing would change
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.birth_year.is_none() && self.country.is_none()
+    }
+}
+
 impl Author {
     pub fn get_id(&self) -> i32 {
         self.id
@@ -75,6 +106,9 @@ mod tests {
             name: new_author.name,
             birth_year: new_author.birth_year,
             country: new_author.country,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
         };
         let display_str = format!("{}", author);
         assert_eq!(display_str, "Author: Machado de Assis (ID: 1)");
@@ -1,9 +1,11 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use pgvector::Vector;
 use serde::Serialize;
 
-use crate::embedding::compute_embedding;
+use crate::embedding::Embedder;
+use crate::models::AuthorSummary;
 use crate::schema::books;
 
 #[derive(Queryable, Selectable, Identifiable, PartialEq, Debug, Clone)]
@@ -15,6 +17,12 @@ pub struct Book {
     pub publication_year: i32,
     pub abstract_text: String,
     pub embedding: Option<Vector>,
+    pub embedding_version: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Last change to a field or to whether the book is deleted; embedding
+    /// it again does not count
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Insertable, Debug)]
@@ -24,22 +32,73 @@ pub struct NewBook {
     pub publication_year: i32,
     pub abstract_text: String,
     pub embedding: Option<Vector>,
+    pub embedding_version: Option<String>,
 }
 
 impl NewBook {
-    pub fn new(title: &str, publication_year: i32, abstract_text: &str) -> Result<Self> {
-        let embedding_vec = compute_embedding(abstract_text)?;
+    pub fn new(
+        title: &str,
+        publication_year: i32,
+        abstract_text: &str,
+        embedder: &dyn Embedder,
+    ) -> Result<Self> {
+        let embedding_vec = embedder.embed(abstract_text)?;
         let embedding = Vector::from(embedding_vec);
 
         Ok(NewBook {
             title: title.to_string(),
-            publication_year: publication_year,
+            publication_year,
             abstract_text: abstract_text.to_string(),
             embedding: Some(embedding),
+            embedding_version: Some(embedder.version().to_string()),
         })
     }
 }
 
+/// Fields of a book to change; `None` leaves a field as it is
+#[derive(AsChangeset, Debug, Default)]
+#[diesel(table_name = books)]
+pub struct BookChanges {
+    pub title: Option<String>,
+    pub publication_year: Option<i32>,
+    pub abstract_text: Option<String>,
+    pub embedding: Option<Vector>,
+    pub embedding_version: Option<String>,
+}
+
+impl BookChanges {
+    /// Changes to `title`, `publication_year` and `abstract_text`, where
+    /// given. A new abstract is embedded again, so the stored vector always
+    /// matches the stored text.
+    pub fn new(
+        title: Option<&str>,
+        publication_year: Option<i32>,
+        abstract_text: Option<&str>,
+        embedder: &dyn Embedder,
+    ) -> Result<Self> {
+        let (embedding, embedding_version) = match abstract_text {
+            Some(text) => (
+                Some(Vector::from(embedder.embed(text)?)),
+                Some(embedder.version().to_string()),
+            ),
+            None => (None, None),
+        };
+
+        Ok(BookChanges {
+            title: title.map(str::to_string),
+            publication_year,
+            abstract_text: abstract_text.map(str::to_string),
+            embedding,
+            embedding_version,
+        })
+    }
+
+    /// Whether nothing would change
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.publication_year.is_none() && self.abstract_text.is_none()
+    }
+}
+
 impl Book {
     pub fn get_id(&self) -> i32 {
         self.id
@@ -61,6 +120,10 @@ impl Book {
         self.embedding.as_ref()
     }
 
+    pub fn get_embedding_version(&self) -> Option<&str> {
+        self.embedding_version.as_deref()
+    }
+
     pub fn has_embedding(&self) -> bool {
         self.embedding.is_some()
     }
@@ -84,22 +147,62 @@ pub struct BookResponse {
     title: String,
     publication_year: i32,
     abstract_text: String,
+    authors: Vec<AuthorSummary>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
 }
 
-impl From<Book> for BookResponse {
-    fn from(book: Book) -> Self {
+impl BookResponse {
+    pub fn with_authors(book: Book, authors: Vec<AuthorSummary>) -> Self {
         BookResponse {
             id: book.get_id(),
             title: book.get_title().to_string(),
             publication_year: book.get_publication_year(),
             abstract_text: book.abstract_text,
+            authors,
+            created_at: book.created_at,
+            updated_at: book.updated_at,
+            deleted_at: book.deleted_at,
         }
     }
 }
 
+/// A book just inserted, which has no authors linked yet
+impl From<Book> for BookResponse {
+    fn from(book: Book) -> Self {
+        BookResponse::with_authors(book, Vec::new())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embedding::HashingEmbedder;
+
+    #[test]
+    fn test_book_changes_embed_new_abstract() {
+        let embedder = HashingEmbedder::new();
+        let changes = BookChanges::new(Some("Dom Casmurro"), None, None, &embedder).unwrap();
+        assert!(!changes.is_empty());
+        assert!(changes.embedding.is_none());
+        assert!(changes.embedding_version.is_none());
+
+        let changes = BookChanges::new(
+            None,
+            None,
+            Some("Bentinho e Capitu crescem juntos."),
+            &embedder,
+        )
+        .unwrap();
+        assert!(changes.embedding.is_some());
+        assert_eq!(
+            changes.embedding_version.as_deref(),
+            Some(embedder.version())
+        );
+
+        assert!(BookChanges::default().is_empty());
+    }
 
     #[test]
     fn test_new_book_creation() {
@@ -107,6 +210,7 @@ mod tests {
             "Dom Casmurro",
             1899,
             "Um clássico da literatura brasileira.",
+            &HashingEmbedder::new(),
         )
         .expect("Failed to create new book");
 
@@ -117,6 +221,10 @@ mod tests {
             "Um clássico da literatura brasileira."
         );
         assert!(new_book.embedding.is_some());
+        assert_eq!(
+            new_book.embedding_version.as_deref(),
+            Some(HashingEmbedder::new().version())
+        );
     }
 
     #[test]
@@ -125,6 +233,7 @@ mod tests {
             "Dom Casmurro",
             1899,
             "Um clássico da literatura brasileira.",
+            &HashingEmbedder::new(),
         )
         .expect("Failed to create new book");
         let book = Book {
@@ -133,6 +242,10 @@ mod tests {
             publication_year: new_book.publication_year,
             abstract_text: new_book.abstract_text,
             embedding: new_book.embedding,
+            embedding_version: new_book.embedding_version,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
         };
         let display_str = format!("{}", book);
         // Note: The original test expected "Has Embedding: false" but the NewBook constructor sets it to Some(embedding), so it should be true.
@@ -148,6 +261,7 @@ mod tests {
             "Dom Casmurro",
             1899,
             "Um clássico da literatura brasileira.",
+            &HashingEmbedder::new(),
         )
         .expect("Failed to create new book");
         let book = Book {
@@ -156,6 +270,10 @@ mod tests {
             publication_year: new_book.publication_year,
             abstract_text: new_book.abstract_text,
             embedding: new_book.embedding,
+            embedding_version: new_book.embedding_version,
+            created_at: DateTime::from_timestamp(1_729_267_200, 123_456_000).unwrap(),
+            updated_at: DateTime::from_timestamp(1_729_267_260, 0).unwrap(),
+            deleted_at: None,
         };
         let book_response = BookResponse::from(book);
         assert_eq!(book_response.id, 1);
@@ -165,5 +283,10 @@ mod tests {
             book_response.abstract_text,
             "Um clássico da literatura brasileira."
         );
+
+        let json = serde_json::to_value(&book_response).unwrap();
+        assert_eq!(json["created_at"], "2024-10-18T16:00:00.123456Z");
+        assert_eq!(json["updated_at"], "2024-10-18T16:01:00Z");
+        assert!(json["deleted_at"].is_null());
     }
 }
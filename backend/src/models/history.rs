@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::schema::record_history;
+
+/// One version of an author or book, written by the database whenever the
+/// record is created, changed, deleted or restored
+#[derive(Queryable, Selectable, Debug, Clone, Serialize)]
+#[diesel(table_name = record_history)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct HistoryEntry {
+    /// Counts the versions of the record from 1
+    pub version: i32,
+    /// `create`, `update`, `delete` or `restore`
+    pub action: String,
+    /// Fields of the record in this version, without its embedding
+    pub data: serde_json::Value,
+    /// Who made the change, as named by the `X-Actor` header
+    pub actor: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Tables whose records keep a history
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordTable {
+    Authors,
+    Books,
+}
+
+impl RecordTable {
+    /// Name of the table, as stored in `record_history.record_table`
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordTable::Authors => "authors",
+            RecordTable::Books => "books",
+        }
+    }
+
+    /// What one record of the table is called in messages
+    pub fn entity(self) -> &'static str {
+        match self {
+            RecordTable::Authors => "Author",
+            RecordTable::Books => "Book",
+        }
+    }
+}
@@ -1,7 +1,9 @@
 pub mod author;
 pub mod book;
 pub mod book_author;
+pub mod history;
 
-pub use author::{Author, NewAuthor};
-pub use book::{Book, BookResponse, NewBook};
+pub use author::{Author, AuthorChanges, AuthorSummary, NewAuthor};
+pub use book::{Book, BookChanges, BookResponse, NewBook};
 pub use book_author::{BookAuthor, NewBookAuthor};
+pub use history::{HistoryEntry, RecordTable};
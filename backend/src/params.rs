@@ -0,0 +1,110 @@
+//! Deserializers for request fields that arrive either in a JSON body or in
+//! a query string. Query strings carry every value as text, and serde hands
+//! them over unparsed to the fields of a flattened struct, so such fields
+//! accept both forms.
+
+use serde::{Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TextOr<T> {
+    Value(T),
+    Text(String),
+}
+
+/// Parses one value from text
+fn parse<T, E>(text: &str) -> Result<T, E>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+    E: serde::de::Error,
+{
+    text.trim().parse().map_err(E::custom)
+}
+
+/// Accepts a value either as itself or as text
+pub fn from_text_or_value<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    match Option::<TextOr<T>>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(TextOr::Value(value)) => Ok(Some(value)),
+        Some(TextOr::Text(text)) => parse(&text).map(Some),
+    }
+}
+
+/// Accepts a list either as itself or as comma-separated text
+/// (`author_ids=1,2,3`)
+pub fn list_from_text_or_value<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    match Option::<TextOr<Vec<T>>>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(TextOr::Value(values)) => Ok(Some(values)),
+        Some(TextOr::Text(text)) if text.trim().is_empty() => Ok(Some(Vec::new())),
+        Some(TextOr::Text(text)) => text
+            .split(',')
+            .map(parse)
+            .collect::<Result<_, _>>()
+            .map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::web::Query;
+
+    fn from_query(query: &str) -> Option<Outer> {
+        Query::<Outer>::from_query(query)
+            .ok()
+            .map(Query::into_inner)
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Inner {
+        #[serde(default, deserialize_with = "from_text_or_value")]
+        year: Option<i32>,
+        #[serde(default, deserialize_with = "list_from_text_or_value")]
+        ids: Option<Vec<i32>>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Outer {
+        name: String,
+        #[serde(flatten)]
+        inner: Inner,
+    }
+
+    #[test]
+    fn test_query_string() {
+        let outer = from_query("name=a&year=1900&ids=1,%202").unwrap();
+        assert_eq!(outer.inner.year, Some(1900));
+        assert_eq!(outer.inner.ids, Some(vec![1, 2]));
+
+        let outer = from_query("name=a").unwrap();
+        assert_eq!(
+            outer.inner,
+            Inner {
+                year: None,
+                ids: None
+            }
+        );
+
+        assert!(from_query("name=a&ids=1,x").is_none());
+    }
+
+    #[test]
+    fn test_json() {
+        let outer: Outer =
+            serde_json::from_str(r#"{"name": "a", "year": 1900, "ids": [1, 2]}"#).unwrap();
+        assert_eq!(outer.inner.year, Some(1900));
+        assert_eq!(outer.inner.ids, Some(vec![1, 2]));
+    }
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+
+use crate::database::pagination::Paginated;
+use crate::error::FieldError;
+
+/// Body of every API response
+#[derive(Serialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub message: String,
+    /// Machine-readable kind of a failure (see [`crate::error::Error::code`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    /// What is wrong with each invalid field of the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
+    /// Cursor of the next page of a list; absent on its last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Results across all pages of a list, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn success(data: T) -> Self {
+        ApiResponse {
+            success: true,
+            data: Some(data),
+            message: "Success".to_string(),
+            code: None,
+            errors: None,
+            next_cursor: None,
+            total: None,
+        }
+    }
+
+    pub fn error(code: &'static str, message: String) -> ApiResponse<()> {
+        ApiResponse {
+            success: false,
+            data: None,
+            message,
+            code: Some(code),
+            errors: None,
+            next_cursor: None,
+            total: None,
+        }
+    }
+}
+
+impl<T> ApiResponse<Vec<T>> {
+    pub fn page(page: Paginated<T>) -> Self {
+        ApiResponse {
+            next_cursor: page.next_cursor,
+            total: page.total,
+            ..ApiResponse::success(page.items)
+        }
+    }
+}
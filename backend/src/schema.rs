@@ -9,6 +9,9 @@ diesel::table! {
         name -> Varchar,
         birth_year -> Int4,
         country -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -22,6 +25,10 @@ diesel::table! {
         publication_year -> Int4,
         abstract_text -> Varchar,
         embedding -> Nullable<Vector>,
+        embedding_version -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -35,6 +42,22 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use pgvector::sql_types::*;
+
+    record_history (id) {
+        id -> Int4,
+        record_table -> Varchar,
+        record_id -> Int4,
+        version -> Int4,
+        action -> Varchar,
+        data -> Jsonb,
+        actor -> Varchar,
+        recorded_at -> Timestamptz,
+    }
+}
+
 diesel::joinable!(books_authors -> authors (author_id));
 diesel::joinable!(books_authors -> books (book_id));
 
@@ -42,4 +65,5 @@ diesel::allow_tables_to_appear_in_same_query!(
     authors,
     books,
     books_authors,
+    record_history,
 );
@@ -0,0 +1,246 @@
+//! Declarative checks for the fields of create requests. A request lists
+//! the rules of each of its fields; every field breaking a rule is reported
+//! at once, answered with 422 and one entry per field.
+
+use chrono::{Datelike, Utc};
+
+use crate::error::{Error, FieldError, Result};
+use crate::models::Author;
+
+/// Earliest birth or publication year accepted; negative years are refused
+pub const MIN_YEAR: i32 = 0;
+pub const MAX_NAME_LENGTH: usize = 200;
+pub const MAX_COUNTRY_LENGTH: usize = 100;
+pub const MAX_TITLE_LENGTH: usize = 300;
+/// Shorter abstracts may have no n-gram to embed, leaving a zero vector
+/// that vector search cannot compare against
+pub const MIN_ABSTRACT_LENGTH: usize = 20;
+pub const MAX_ABSTRACT_LENGTH: usize = 20_000;
+
+/// Requests that check their own fields before being acted on
+pub trait Validate {
+    /// Applies the rules of every field to `validator`
+    fn rules(&self, validator: Validator) -> Validator;
+
+    fn validate(&self) -> Result<()> {
+        self.rules(Validator::new()).finish()
+    }
+}
+
+/// A rule for a text field, applied to the value with surrounding
+/// whitespace trimmed; lengths count characters
+#[derive(Debug, Clone, Copy)]
+pub enum Text {
+    NotBlank,
+    MinLength(usize),
+    MaxLength(usize),
+}
+
+impl Text {
+    fn check(self, value: &str) -> Option<String> {
+        let length = value.trim().chars().count();
+        match self {
+            Text::NotBlank if length == 0 => Some("must not be blank".to_string()),
+            Text::MinLength(min) if length < min => {
+                Some(format!("must have at least {} characters", min))
+            }
+            Text::MaxLength(max) if length > max => {
+                Some(format!("must have at most {} characters", max))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Collects the first broken rule of each field
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+    /// Path of the part of the request being checked, e.g. `authors[1]`
+    prefix: Option<String>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Validator::default()
+    }
+
+    fn push(&mut self, field: &str, message: String) {
+        let field = match &self.prefix {
+            Some(prefix) => format!("{}.{}", prefix, field),
+            None => field.to_string(),
+        };
+        self.errors.push(FieldError { field, message });
+    }
+
+    pub fn text(mut self, field: &str, value: &str, rules: &[Text]) -> Self {
+        if let Some(message) = rules.iter().find_map(|rule| rule.check(value)) {
+            self.push(field, message);
+        }
+        self
+    }
+
+    /// A year from [`MIN_YEAR`] to the current one
+    pub fn year(mut self, field: &str, value: i32) -> Self {
+        let current = current_year();
+        if !(MIN_YEAR..=current).contains(&value) {
+            self.push(
+                field,
+                format!("must be between {} and {}", MIN_YEAR, current),
+            );
+        }
+        self
+    }
+
+    /// A book published in `publication_year` cannot predate the birth of
+    /// any of its `authors`
+    pub fn published_after_births(
+        mut self,
+        field: &str,
+        publication_year: i32,
+        authors: &[Author],
+    ) -> Self {
+        let unborn: Vec<String> = authors
+            .iter()
+            .filter(|author| author.birth_year > publication_year)
+            .map(|author| format!("{} (born {})", author.name, author.birth_year))
+            .collect();
+        if !unborn.is_empty() {
+            self.push(
+                field,
+                format!(
+                    "a book published in {} cannot be written by {}",
+                    publication_year,
+                    unborn.join(", ")
+                ),
+            );
+        }
+        self
+    }
+
+    /// Adds a failure found by a check of its own
+    pub fn fail(mut self, field: &str, message: impl Into<String>) -> Self {
+        self.push(field, message.into());
+        self
+    }
+
+    /// Applies the rules of `part` of the request, naming its fields
+    /// `<prefix>.<field>`
+    pub fn nested(mut self, prefix: &str, part: &impl Validate) -> Self {
+        let path = match &self.prefix {
+            Some(outer) => format!("{}.{}", outer, prefix),
+            None => prefix.to_string(),
+        };
+        let outer = self.prefix.replace(path);
+        let mut validator = part.rules(self);
+        validator.prefix = outer;
+        validator
+    }
+
+    pub fn finish(self) -> Result<()> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidFields(self.errors))
+        }
+    }
+}
+
+/// The current year in UTC
+pub fn current_year() -> i32 {
+    Utc::now().year()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(result: Result<()>) -> Vec<String> {
+        match result {
+            Ok(()) => Vec::new(),
+            Err(Error::InvalidFields(errors)) => errors.into_iter().map(|e| e.field).collect(),
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_text_rules() {
+        let rules = [Text::NotBlank, Text::MaxLength(5)];
+        let result = Validator::new()
+            .text("blank", "   ", &rules)
+            .text("long", "Machado", &rules)
+            .text("padded", "  Assis  ", &rules)
+            .finish();
+        assert_eq!(fields(result), vec!["blank", "long"]);
+
+        let short = Validator::new()
+            .text(
+                "abstract_text",
+                "Curto",
+                &[Text::MinLength(MIN_ABSTRACT_LENGTH)],
+            )
+            .finish();
+        assert_eq!(fields(short), vec!["abstract_text"]);
+    }
+
+    struct Named(&'static str);
+
+    impl Validate for Named {
+        fn rules(&self, validator: Validator) -> Validator {
+            validator.text("name", self.0, &[Text::NotBlank])
+        }
+    }
+
+    #[test]
+    fn test_nested() {
+        let result = Validator::new()
+            .nested("authors[0]", &Named("Machado"))
+            .nested("authors[1]", &Named(" "))
+            .fail("authors", "must not repeat")
+            .finish();
+        assert_eq!(fields(result), vec!["authors[1].name", "authors"]);
+    }
+
+    #[test]
+    fn test_year_bounds() {
+        let result = Validator::new()
+            .year("ancient", MIN_YEAR)
+            .year("negative", -1)
+            .year("now", current_year())
+            .year("future", current_year() + 1)
+            .finish();
+        assert_eq!(fields(result), vec!["negative", "future"]);
+    }
+
+    #[test]
+    fn test_published_after_births() {
+        let author = |name: &str, birth_year| Author {
+            id: 1,
+            name: name.to_string(),
+            birth_year,
+            country: "Brazil".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        };
+        let authors = [author("Machado de Assis", 1839), author("Clarice", 1920)];
+
+        assert!(
+            Validator::new()
+                .published_after_births("authors_ids", 1950, &authors)
+                .finish()
+                .is_ok()
+        );
+        match Validator::new()
+            .published_after_births("authors_ids", 1899, &authors)
+            .finish()
+        {
+            Err(Error::InvalidFields(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert!(errors[0].message.contains("Clarice (born 1920)"));
+                assert!(!errors[0].message.contains("Machado"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}